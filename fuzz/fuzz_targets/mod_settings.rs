@@ -0,0 +1,13 @@
+#![no_main]
+
+use std::convert::{TryFrom, TryInto};
+
+use factorio_file_parser::ModSettings;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Anything that parses should also serialise without panicking
+    if let Ok(ms) = ModSettings::try_from(data) {
+        let _: Result<Vec<u8>, _> = ms.try_into();
+    }
+});
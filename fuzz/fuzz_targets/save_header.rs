@@ -0,0 +1,14 @@
+#![no_main]
+
+use std::convert::{TryFrom, TryInto};
+
+use factorio_file_parser::{LevelInit, SaveHeader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Anything that parses should also serialise without panicking
+    if let Ok(header) = SaveHeader::try_from(data) {
+        let _: Result<Vec<u8>, _> = header.try_into();
+    }
+    let _ = LevelInit::try_from(data);
+});
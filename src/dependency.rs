@@ -0,0 +1,258 @@
+use crate::error::{Error, Result};
+use crate::schema::{SaveHeader, SaveHeaderMod, Version48};
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// One entry of the `dependencies` list in a mod's `info.json`,
+/// e.g. `? some-mod >= 1.2.0`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct Dependency {
+    pub kind: DependencyKind,
+    pub name: String,
+    pub requirement: Option<VersionRequirement>,
+}
+
+/// Set by the prefix before the mod name
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum DependencyKind {
+    /// No prefix
+    Required,
+    /// `?`
+    Optional,
+    /// `(?)`, optional but not shown in the mod GUI
+    HiddenOptional,
+    /// `!`, the mods can't be loaded together at any version
+    Incompatible,
+    /// `~`, required but doesn't affect load order
+    NoLoadOrder,
+}
+
+impl DependencyKind {
+    fn prefix(&self) -> &'static str {
+        match self {
+            DependencyKind::Required => "",
+            DependencyKind::Optional => "?",
+            DependencyKind::HiddenOptional => "(?)",
+            DependencyKind::Incompatible => "!",
+            DependencyKind::NoLoadOrder => "~",
+        }
+    }
+}
+
+/// Version constraint following the mod name, e.g. `>= 1.2.0`
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub struct VersionRequirement {
+    pub comparison: Comparison,
+    pub version: Version48,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn operator(&self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+}
+
+impl VersionRequirement {
+    pub fn matches(&self, version: &Version48) -> bool {
+        match self.comparison {
+            Comparison::Less => version < &self.version,
+            Comparison::LessOrEqual => version <= &self.version,
+            Comparison::Equal => version == &self.version,
+            Comparison::GreaterOrEqual => version >= &self.version,
+            Comparison::Greater => version > &self.version,
+        }
+    }
+}
+
+impl Dependency {
+    /// Whether `version` of the mod meets the version requirement, if there is one
+    pub fn matches(&self, version: &Version48) -> bool {
+        self.requirement
+            .as_ref()
+            .is_none_or(|requirement| requirement.matches(version))
+    }
+
+    /// Whether a set of loaded mods, such as [`SaveHeader::mods`], is acceptable to this dependency
+    pub fn is_satisfied_by(&self, mods: &[SaveHeaderMod]) -> bool {
+        let loaded = mods.iter().find(|m| m.name == self.name);
+        match (self.kind, loaded) {
+            (DependencyKind::Incompatible, loaded) => loaded.is_none(),
+            (DependencyKind::Required, None) | (DependencyKind::NoLoadOrder, None) => false,
+            (DependencyKind::Optional, None) | (DependencyKind::HiddenOptional, None) => true,
+            (_, Some(m)) => self.matches(&m.version),
+        }
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::Syntax(format!("Invalid dependency '{}'", s));
+
+        // Prefix first, checking `(?)` before `?`
+        let trimmed = s.trim();
+        let (kind, rest) = [
+            DependencyKind::HiddenOptional,
+            DependencyKind::Optional,
+            DependencyKind::Incompatible,
+            DependencyKind::NoLoadOrder,
+        ]
+        .iter()
+        .find_map(|kind| {
+            trimmed
+                .strip_prefix(kind.prefix())
+                .map(|rest| (*kind, rest))
+        })
+        .unwrap_or((DependencyKind::Required, trimmed));
+
+        // Mod names can contain spaces but never comparison operators
+        let (name, requirement) = match rest.find(['<', '=', '>']) {
+            None => (rest.trim(), None),
+            Some(i) => {
+                let (name, constraint) = rest.split_at(i);
+                let (comparison, version) = [
+                    Comparison::LessOrEqual,
+                    Comparison::GreaterOrEqual,
+                    Comparison::Less,
+                    Comparison::Equal,
+                    Comparison::Greater,
+                ]
+                .iter()
+                .find_map(|c| constraint.strip_prefix(c.operator()).map(|v| (*c, v)))
+                .ok_or_else(invalid)?;
+                let version = parse_requirement_version(version.trim()).ok_or_else(invalid)?;
+                (
+                    name.trim(),
+                    Some(VersionRequirement {
+                        comparison,
+                        version,
+                    }),
+                )
+            }
+        };
+
+        // A version constraint on an incompatibility would have no effect
+        if name.is_empty() || (kind == DependencyKind::Incompatible && requirement.is_some()) {
+            return Err(invalid());
+        }
+
+        Ok(Dependency {
+            kind,
+            name: name.to_owned(),
+            requirement,
+        })
+    }
+}
+
+/// Dependency versions may leave off trailing parts, e.g. `base >= 1.1`
+fn parse_requirement_version(s: &str) -> Option<Version48> {
+    let parts = s
+        .split('.')
+        .map(|part| part.parse::<u16>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match parts[..] {
+        [main, major] => Some(Version48::new(main, major, 0)),
+        [main, major, minor] => Some(Version48::new(main, major, minor)),
+        _ => None,
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = self.kind.prefix();
+        if !prefix.is_empty() {
+            write!(f, "{} ", prefix)?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(requirement) = &self.requirement {
+            write!(
+                f,
+                " {} {}",
+                requirement.comparison.operator(),
+                requirement.version
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl SaveHeader {
+    /// Dependencies from `dependencies` that the save's mods don't satisfy
+    pub fn unmet_dependencies<'d>(&self, dependencies: &'d [Dependency]) -> Vec<&'d Dependency> {
+        dependencies
+            .iter()
+            .filter(|d| !d.is_satisfied_by(&self.mods))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_parse_dependencies() -> Result<()> {
+        let d: Dependency = "? some-mod >= 1.2.0".parse()?;
+        assert_eq!(DependencyKind::Optional, d.kind);
+        assert_eq!("some-mod", d.name);
+        assert!(d.matches(&Version48::new(1, 2, 0)));
+        assert!(d.matches(&Version48::new(1, 10, 0)));
+        assert!(!d.matches(&Version48::new(1, 1, 9)));
+
+        let d: Dependency = "(?)hidden mod with spaces<2.0".parse()?;
+        assert_eq!(DependencyKind::HiddenOptional, d.kind);
+        assert_eq!("hidden mod with spaces", d.name);
+        assert!(!d.matches(&Version48::new(2, 0, 0)));
+
+        assert_eq!(
+            DependencyKind::Incompatible,
+            "! incompatible".parse::<Dependency>()?.kind
+        );
+        assert_eq!(
+            DependencyKind::NoLoadOrder,
+            "~ no-load-order".parse::<Dependency>()?.kind
+        );
+        assert_eq!(DependencyKind::Required, "base".parse::<Dependency>()?.kind);
+
+        for s in &[
+            "? some-mod >= 1.2.0",
+            "(?) a < 1.0.0",
+            "! b",
+            "~ c = 0.1.2",
+            "d > 3.0.0",
+        ] {
+            assert_eq!(*s, s.parse::<Dependency>()?.to_string());
+        }
+
+        for bad in &[
+            "",
+            "?",
+            ">= 1.0",
+            "mod >=",
+            "mod => 1.0",
+            "mod >= 1",
+            "mod >= 1.x.0",
+            "! mod >= 1.0.0",
+        ] {
+            assert!(bad.parse::<Dependency>().is_err(), "{}", bad);
+        }
+
+        Ok(())
+    }
+}
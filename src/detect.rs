@@ -0,0 +1,107 @@
+use crate::error::{Error, Result};
+use crate::schema::{is_level_init_path, ModSettings, SaveHeader, Version};
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+/// Local file header signature that every zip, and so every save, starts with
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Anything above this is treated as garbage rather than a game version
+const MAX_PLAUSIBLE_MAIN_VERSION: u16 = 9;
+
+/// Kinds of file that [`detect_format`] can recognise
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum FileKind {
+    /// `mod-settings.dat`, from any version
+    ModSettings,
+    /// `level-init.dat`, or anything else starting with a [`SaveHeader`]
+    SaveHeader,
+    /// A save zip containing `level-init.dat`
+    SaveZip,
+}
+
+/// A file parsed by [`parse_any`], depending on what it was detected as
+#[derive(Clone, Debug)]
+pub enum AnyFactorioFile {
+    ModSettings(ModSettings),
+    /// Header at the start of `level-init.dat`. The rest of the file isn't read,
+    /// see [`LevelInit`](crate::LevelInit) for that.
+    SaveHeader(SaveHeader),
+    /// Header of the save's `level-init.dat`
+    SaveZip(SaveHeader),
+}
+
+impl AnyFactorioFile {
+    pub fn kind(&self) -> FileKind {
+        match self {
+            AnyFactorioFile::ModSettings(_) => FileKind::ModSettings,
+            AnyFactorioFile::SaveHeader(_) => FileKind::SaveHeader,
+            AnyFactorioFile::SaveZip(_) => FileKind::SaveZip,
+        }
+    }
+
+    /// The save header, if the file had one
+    pub fn save_header(&self) -> Option<&SaveHeader> {
+        match self {
+            AnyFactorioFile::ModSettings(_) => None,
+            AnyFactorioFile::SaveHeader(header) | AnyFactorioFile::SaveZip(header) => Some(header),
+        }
+    }
+}
+
+/// Guesses what kind of file `input` is from its structure, without fully parsing it.
+/// Returns `None` if it doesn't look like any of the supported formats.
+pub fn detect_format(input: &[u8]) -> Option<FileKind> {
+    if input.starts_with(ZIP_MAGIC) {
+        // Mod zips are far more common than saves, so look for the save's level-init.dat
+        let archive = zip::ZipArchive::new(Cursor::new(input)).ok()?;
+        return archive
+            .file_names()
+            .any(is_level_init_path)
+            .then_some(FileKind::SaveZip);
+    }
+
+    if looks_like_mod_settings(input) {
+        return Some(FileKind::ModSettings);
+    }
+
+    // The header has no magic of its own, so it has to read cleanly up to the mod list
+    let header = SaveHeader::parse_summary(input).ok()?;
+    if is_plausible(&header.factorio_version) {
+        Some(FileKind::SaveHeader)
+    } else {
+        None
+    }
+}
+
+/// Parses `input` as whichever format [`detect_format`] recognises it as
+pub fn parse_any(input: &[u8]) -> Result<AnyFactorioFile> {
+    match detect_format(input) {
+        Some(FileKind::ModSettings) => {
+            ModSettings::try_from(input).map(AnyFactorioFile::ModSettings)
+        }
+        Some(FileKind::SaveHeader) => SaveHeader::try_from(input).map(AnyFactorioFile::SaveHeader),
+        Some(FileKind::SaveZip) => {
+            SaveHeader::from_save_zip(Cursor::new(input)).map(AnyFactorioFile::SaveZip)
+        }
+        None => Err(Error::Syntax("Unrecognised file format".to_owned())),
+    }
+}
+
+fn looks_like_mod_settings(input: &[u8]) -> bool {
+    // The settings dictionary follows the version and a false sentinel, or comes first
+    // in files from before 0.17. Either way it always has exactly three sections.
+    let dictionary = match ModSettings::peek_version(input) {
+        Ok(None) => input,
+        Ok(Some(version)) if is_plausible(&version) => match input.get(8..) {
+            Some([0, rest @ ..]) => rest,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    matches!(dictionary, [5, 0..=1, 3, 0, 0, 0, ..])
+}
+
+fn is_plausible(version: &Version) -> bool {
+    version.main() <= MAX_PLAUSIBLE_MAIN_VERSION
+}
@@ -0,0 +1,62 @@
+//! Serde helpers for [`Version`](crate::Version) and [`Version48`](crate::Version48)
+//! in their dotted string form, e.g. `"1.1.110"`, rather than as structs.
+//!
+//! ```
+//! #[derive(serde::Deserialize, serde::Serialize)]
+//! struct Server {
+//!     #[serde(with = "factorio_file_parser::dotted")]
+//!     version: factorio_file_parser::Version48,
+//! }
+//! ```
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serializer};
+use std::{fmt::Display, str::FromStr};
+
+pub fn serialize<T, S>(version: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(version)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> std::result::Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(D::Error::custom)
+}
+
+/// The same, for an optional version that is left out when `None`. Use
+/// alongside `#[serde(default, skip_serializing_if = "Option::is_none")]`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<T, S>(
+        version: &Option<T>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        match version {
+            Some(version) => serializer.collect_str(version),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| s.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
@@ -6,11 +6,23 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     // Generic variants created by internal data structures
     Message(String),
+    /// Another error, along with where in the input it happened
+    Context {
+        /// Byte offset into the input
+        offset: usize,
+        /// Logical location, e.g. `runtime-global/my-mod-setting/value` or `mods[12].version`
+        path: String,
+        source: Box<Error>,
+    },
 
     // Format-specific variants
     ByteSlicingError,
     Eof,
+    Io(String),
+    LimitExceeded(String),
+    NotZip(String),
     OutOfRange,
+    SaveHeaderMissing,
     Syntax(String),
     TrailingBytes,
     Utf8(std::str::Utf8Error),
@@ -20,15 +32,48 @@ impl Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::Message(msg) => write!(f, "factorio-file-parser::Error::Message({})", msg),
+            Error::Context {
+                offset,
+                path,
+                source,
+            } => write!(
+                f,
+                "factorio-file-parser::Error::Context({} at byte {}, path '{}')",
+                source, offset, path
+            ),
             Error::ByteSlicingError => write!(f, "factorio-file-parser::Error::ByteSlicingError"),
             Error::Eof => write!(f, "factorio-file-parser::Error::Eof"),
+            Error::Io(msg) => write!(f, "factorio-file-parser::Error::Io({})", msg),
+            Error::LimitExceeded(msg) => {
+                write!(f, "factorio-file-parser::Error::LimitExceeded({})", msg)
+            }
+            Error::NotZip(msg) => write!(f, "factorio-file-parser::Error::NotZip({})", msg),
             Error::OutOfRange => write!(f, "factorio-file-parser::Error::OutOfRange"),
+            Error::SaveHeaderMissing => write!(f, "factorio-file-parser::Error::SaveHeaderMissing"),
             Error::Syntax(msg) => write!(f, "factorio-file-parser::Error::Syntax({})", msg),
             Error::TrailingBytes => write!(f, "factorio-file-parser::Error::TrailingBytes"),
-            Error::Utf8(utf8_error) => write!(f, "factorio-file-parser::Error::Utf8({})", utf8_error),
-            
+            Error::Utf8(utf8_error) => {
+                write!(f, "factorio-file-parser::Error::Utf8({})", utf8_error)
+            }
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl Error {
+    /// The underlying error, with any [`Error::Context`] stripped
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.kind(),
+            other => other,
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
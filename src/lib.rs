@@ -1,5 +1,20 @@
+mod dependency;
+mod detect;
+pub mod dotted;
 mod error;
+mod mod_list;
+mod options;
 mod schema;
+mod settings;
 
+pub use crate::dependency::{Comparison, Dependency, DependencyKind, VersionRequirement};
+pub use crate::detect::{detect_format, parse_any, AnyFactorioFile, FileKind};
 pub use crate::error::Error;
-pub use crate::schema::{ModSettings, PropertyTree, SaveHeader, SaveHeaderMod, Version};
+pub use crate::mod_list::{ModList, ModListEntry};
+pub use crate::options::{ParseLimits, ParseMode, ParseOptions, Parsed};
+pub use crate::schema::{
+    AllowedCommands, AutoplaceControl, BoundingBox, BuildNumber, CliffSettings, Difficulty,
+    LevelInit, MapGenSettings, MapPosition, ModSettings, PropertyTree, SaveHeader, SaveHeaderMod,
+    Version, Version48,
+};
+pub use crate::settings::{Color, SettingValue, SettingsSection};
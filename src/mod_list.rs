@@ -0,0 +1,85 @@
+use crate::error::{Error, Result};
+use crate::schema::{SaveHeader, Version48};
+use std::io::{Read, Write};
+
+/// Contents of `mod-list.json`, the mods the game knows about and whether each is enabled
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModList {
+    pub mods: Vec<ModListEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModListEntry {
+    pub name: String,
+    pub enabled: bool,
+    /// Loads this version rather than the latest one installed
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "crate::dotted::option"
+    )]
+    pub version: Option<Version48>,
+}
+
+impl ModList {
+    /// Parses `mod-list.json` as it is read from `reader`
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        serde_json::from_reader(reader).map_err(json_error)
+    }
+
+    /// Writes `mod-list.json` to `writer`, indented like the game does
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        serde_json::to_writer_pretty(writer, self).map_err(json_error)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModListEntry> {
+        self.mods.iter().find(|m| m.name == name)
+    }
+
+    /// Mod list with exactly the mods of a save enabled, each pinned to the version the save used
+    pub fn from_save_header(header: &SaveHeader) -> Self {
+        let mut mod_list = ModList::default();
+        mod_list.sync_to_save_header(header);
+        mod_list
+    }
+
+    /// Enables exactly the mods of a save, pinned to the versions the save used.
+    ///
+    /// Other mods are kept in the list but disabled, and their pinned versions are left alone.
+    pub fn sync_to_save_header(&mut self, header: &SaveHeader) {
+        for entry in &mut self.mods {
+            entry.enabled = false;
+        }
+
+        for save_mod in &header.mods {
+            let version = Some(save_mod.version.clone());
+            match self.mods.iter_mut().find(|m| m.name == save_mod.name) {
+                Some(entry) => {
+                    entry.enabled = true;
+                    entry.version = version;
+                }
+                None => self.mods.push(ModListEntry {
+                    name: save_mod.name.clone(),
+                    enabled: true,
+                    version,
+                }),
+            }
+        }
+    }
+}
+
+fn json_error(e: serde_json::Error) -> Error {
+    if e.is_io() {
+        Error::Io(e.to_string())
+    } else if e.is_eof() {
+        Error::Eof
+    } else {
+        Error::Syntax(e.to_string())
+    }
+}
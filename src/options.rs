@@ -0,0 +1,72 @@
+/// How to treat bytes left over once a format has been fully parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Fail with [`Error::TrailingBytes`](crate::Error::TrailingBytes) if any bytes remain
+    Strict,
+    /// Stop at the end of the format and ignore anything after it
+    Lenient,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    pub mode: ParseMode,
+    pub limits: ParseLimits,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        ParseOptions {
+            mode: ParseMode::Strict,
+            limits: ParseLimits::default(),
+        }
+    }
+
+    pub fn lenient() -> Self {
+        ParseOptions {
+            mode: ParseMode::Lenient,
+            limits: ParseLimits::default(),
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::strict()
+    }
+}
+
+/// Bounds on what a parser will accept, to protect against crafted input.
+/// Exceeding any of these fails with [`Error::LimitExceeded`](crate::Error::LimitExceeded).
+///
+/// The defaults are far above anything the game produces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseLimits {
+    /// Maximum PropertyTree nesting depth
+    pub max_depth: usize,
+    /// Maximum number of entries in a single list, dictionary or mod list
+    pub max_entries: usize,
+    /// Maximum length of a single string, in bytes
+    pub max_string_len: usize,
+    /// Maximum memory used by parsed values, in bytes.
+    /// This is an estimate based on string lengths and element sizes.
+    pub max_total_alloc: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_depth: 128,
+            max_entries: 1 << 20,
+            max_string_len: 16 << 20,
+            max_total_alloc: 256 << 20,
+        }
+    }
+}
+
+/// A parsed value along with where parsing stopped
+#[derive(Clone, Debug)]
+pub struct Parsed<T> {
+    pub value: T,
+    /// Byte offset just past the end of the parsed value
+    pub offset: usize,
+}
@@ -1,112 +1,121 @@
 use crate::error::{Error, Result};
-use std::fmt::{Debug, Display};
-use std::io::Read;
+use crate::options::{ParseLimits, ParseMode, ParseOptions, Parsed};
+use std::fmt::{Debug, Display, Write as _};
+use std::io::{ErrorKind, Read, Seek, Write};
 use std::{
     collections::HashMap,
     convert::{TryFrom, TryInto},
+    fs::File,
+    mem::size_of,
+    path::Path,
+    str::FromStr,
 };
 
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct ModSettings {
-    pub version: Version,
+    /// Game version the file was written by, or `None` for files from before
+    /// 0.17 which don't record it
+    pub version: Option<Version>,
+    /// "Any-type" flag on the dictionary holding the three sections, see
+    /// [`PropertyTree::AnyType`]. The game leaves it unset.
+    #[serde(default)]
+    pub any_type: bool,
     pub startup: PropertyTree,
     pub runtime_global: PropertyTree,
     pub runtime_per_user: PropertyTree,
 }
 
-impl TryFrom<&[u8]> for ModSettings {
-    type Error = Error;
+impl ModSettings {
+    /// Parses `mod-settings.dat`, with `options` controlling how anything after the settings is handled
+    pub fn parse(input: &[u8], options: &ParseOptions) -> Result<Parsed<Self>> {
+        ModSettings::from_reader_with_options(input, options)
+    }
 
-    fn try_from(input: &[u8]) -> Result<Self> {
-        let mut d = Deserialiser { byte_slice: input };
+    /// Parses `mod-settings.dat` as it is read from `reader`.
+    ///
+    /// Nothing should follow the settings, so this reads until `reader` reports EOF.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        ModSettings::from_reader_with_options(reader, &ParseOptions::strict())
+            .map(|parsed| parsed.value)
+    }
 
-        // First is 8 bytes representing game version
-        let version = d.parse_version()?;
+    /// Parses `mod-settings.dat` as it is read from `reader`, with `options` controlling how
+    /// anything after the settings is handled. Lenient mode stops reading at the end of the settings.
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<Parsed<Self>> {
+        let mut d = Deserialiser::new(reader, options.limits.clone());
+        let mod_settings = d.parse_mod_settings().map_err(|e| d.context(e))?;
+        d.finish(mod_settings, options.mode)
+    }
 
-        // Next is a single byte always set to false (not 1)
-        let false_sentinel = d.parse_bool()?;
-        if false_sentinel {
-            return Err(Error::Syntax(
-                "After-version sentinel expected to be false, got true".to_owned(),
-            ));
-        }
+    /// Writes `mod-settings.dat` to `writer`
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        Serialiser::new(writer).write_mod_settings(self)
+    }
 
-        // Then is a dictionary-type PropertyTree with empty key
-        // This contains the three settings sections
-        let startup;
-        let runtime_global;
-        let runtime_per_user;
-        match d.parse_property_tree()? {
-            PropertyTree::Dictionary(dict) => {
-                let mut dict: HashMap<String, PropertyTree> = dict.into_iter().collect();
-                match dict.remove("startup") {
-                    None => {
-                        return Err(Error::Syntax(
-                            "Settings section 'startup' missing".to_owned(),
-                        ))
-                    }
-                    Some(d) => startup = d,
-                };
-                match dict.remove("runtime-global") {
-                    None => {
-                        return Err(Error::Syntax(
-                            "Settings section 'runtime-global' missing".to_owned(),
-                        ))
-                    }
-                    Some(d) => runtime_global = d,
-                };
-                match dict.remove("runtime-per-user") {
-                    None => {
-                        return Err(Error::Syntax(
-                            "Settings section 'runtime-per-user' missing".to_owned(),
-                        ))
-                    }
-                    Some(d) => runtime_per_user = d,
-                };
-            }
-            _ => {
-                return Err(Error::Syntax(
-                    "Top-level PropertyTree not dictionary type".to_owned(),
-                ))
-            }
-        }
+    /// Serialises to the bytes of `mod-settings.dat`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
 
-        // Should be at EOF now
-        if let Err(Error::Eof) = d.peek_u8() {
-            Ok(ModSettings {
-                version: version.into(),
-                startup,
-                runtime_global,
-                runtime_per_user,
-            })
-        } else {
-            Err(Error::TrailingBytes)
-        }
+    /// Copy of these settings for a different game version, with values
+    /// re-encoded the way that version stores them.
+    ///
+    /// Integers become doubles before Factorio 2.0, failing if one can't be
+    /// stored exactly. Doubles are left alone going the other way, as there's
+    /// no telling which of them belong to int settings. Files from before 0.17
+    /// are upgraded to the layout with a version.
+    pub fn convert_to(&self, version: Version) -> Result<ModSettings> {
+        let encoding = Encoding::for_version(&version);
+        let mut converted = self.clone();
+        converted.version = Some(version);
+        converted.startup.convert_to(encoding, "/startup")?;
+        converted
+            .runtime_global
+            .convert_to(encoding, "/runtime-global")?;
+        converted
+            .runtime_per_user
+            .convert_to(encoding, "/runtime-per-user")?;
+        Ok(converted)
     }
 }
 
-impl TryInto<Vec<u8>> for ModSettings {
-    type Error = Error;
+impl ModSettings {
+    /// Reads just the game version from the start of `mod-settings.dat`, or
+    /// `None` for files from before 0.17 which don't record it
+    pub fn peek_version(input: &[u8]) -> Result<Option<Version>> {
+        let mut d = Deserialiser::new(input, ParseLimits::default());
+        match d.parse_mod_settings_start().map_err(|e| d.context(e))? {
+            ModSettingsStart::Version(version) => Ok(Some(version)),
+            ModSettingsStart::Legacy { .. } => Ok(None),
+        }
+    }
 
-    fn try_into(self) -> Result<Vec<u8>> {
-        let mut s = Serialiser::new();
+    pub(crate) fn encoding(&self) -> Encoding {
+        self.version
+            .as_ref()
+            .map(Encoding::for_version)
+            .unwrap_or_default()
+    }
+}
 
-        // Write the version first
-        s.write_version(u64::from(self.version));
+impl TryFrom<&[u8]> for ModSettings {
+    type Error = Error;
 
-        // Next is a bool always set to false
-        s.write_bool(false);
+    fn try_from(input: &[u8]) -> Result<Self> {
+        ModSettings::from_reader(input)
+    }
+}
 
-        // Construct our top-level property tree, then write it
-        let mut dict = Vec::with_capacity(3);
-        dict.push(("startup".to_owned(), self.startup));
-        dict.push(("runtime-global".to_owned(), self.runtime_global));
-        dict.push(("runtime-per-user".to_owned(), self.runtime_per_user));
-        let top_level = PropertyTree::Dictionary(dict);
-        s.write_property_tree(top_level)?;
+impl TryInto<Vec<u8>> for ModSettings {
+    type Error = Error;
 
-        // Done
-        Ok(s.bytes)
+    fn try_into(self) -> Result<Vec<u8>> {
+        self.to_bytes()
     }
 }
 
@@ -119,7 +128,7 @@ pub struct SaveHeader {
     pub name: String,
     /// Name of the base mod, should always be `base`
     pub base_mod: String,
-    pub difficulty: u8,
+    pub difficulty: Difficulty,
     // not sure??
     pub finished: bool,
     /// Whether the victory condition has been satisfied
@@ -139,7 +148,9 @@ pub struct SaveHeader {
     /// build of the game this save was loaded from
     pub loaded_from_build: BuildNumber,
     /// whether commands are allowed
-    pub allowed_commands: bool,
+    pub allowed_commands: AllowedCommands,
+    /// 4 bytes introduced in 2.0 with unknown meaning, `None` before 2.0
+    pub unknown_2_0: Option<[u8; 4]>,
     /// list of mods attached to the save
     pub mods: Vec<SaveHeaderMod>,
 }
@@ -148,86 +159,244 @@ impl TryFrom<&[u8]> for SaveHeader {
     type Error = Error;
 
     fn try_from(input: &[u8]) -> Result<Self> {
-        let mut d = Deserialiser { byte_slice: input };
+        // level-init.dat continues after the header, so stop at the end of the mod list
+        SaveHeader::from_reader(input)
+    }
+}
 
-        // First is 8 bytes representing game version
-        let factorio_version = d.parse_version()?;
+impl TryInto<Vec<u8>> for SaveHeader {
+    type Error = Error;
 
-        // Next is a single unused byte
-        let _ = d.parse_bool()?;
+    fn try_into(self) -> Result<Vec<u8>> {
+        self.to_bytes()
+    }
+}
 
-        let campaign = d.parse_string_saveheader()?;
+impl SaveHeader {
+    /// Parses a save header, with `options` controlling how anything after the mod list is handled
+    pub fn parse(input: &[u8], options: &ParseOptions) -> Result<Parsed<Self>> {
+        SaveHeader::from_reader_with_options(input, options)
+    }
 
-        let name = d.parse_string_saveheader()?;
+    /// Parses a save header as it is read from `reader`, stopping at the end of the mod list
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        SaveHeader::from_reader_with_options(reader, &ParseOptions::lenient())
+            .map(|parsed| parsed.value)
+    }
 
-        let base_mod = d.parse_string_saveheader()?;
+    /// Parses a save header as it is read from `reader`, with `options` controlling how
+    /// anything after the mod list is handled. Strict mode reads until `reader` reports EOF.
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<Parsed<Self>> {
+        let mut d = Deserialiser::new(reader, options.limits.clone());
+        let header = d.parse_save_header().map_err(|e| d.context(e))?;
+        d.finish(header, options.mode)
+    }
 
-        // Next is a number representing difficulty
-        let difficulty = d.next_u8()?;
+    /// Writes the save header to `writer`
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        Serialiser::new(writer).write_save_header(self)
+    }
 
-        let finished = d.parse_bool()?;
+    /// Serialises to the bytes at the start of `level-init.dat`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
 
-        let player_won = d.parse_bool()?;
+    /// Reads just the game version from the start of a save header or `level-init.dat`
+    pub fn peek_version(input: &[u8]) -> Result<Version> {
+        let mut d = Deserialiser::new(input, ParseLimits::default());
+        d.field("factorio_version", Deserialiser::parse_version)
+            .map_err(|e| d.context(e))
+    }
 
-        let next_level = d.parse_string_saveheader()?;
+    /// Parses a save header up to, but not including, the mod list, leaving
+    /// [`SaveHeader::mods`] empty. Cheaper than a full parse for saves with many mods.
+    pub fn parse_summary(input: &[u8]) -> Result<SaveHeader> {
+        let mut d = Deserialiser::new(input, ParseLimits::default());
+        d.parse_save_header_summary().map_err(|e| d.context(e))
+    }
 
-        let can_continue = d.parse_bool()?;
+    /// Value of [`SaveHeader::unknown_2_0`] seen in every 2.0 save so far
+    pub const EXPECTED_UNKNOWN_2_0: [u8; 4] = [0x00, 0x00, 0xA0, 0x00];
 
-        let finished_but_continuing = d.parse_bool()?;
+    /// Whether the unknown 2.0 bytes differ from [`SaveHeader::EXPECTED_UNKNOWN_2_0`],
+    /// which may indicate a layout change in a newer release
+    pub fn has_unexpected_unknown_2_0(&self) -> bool {
+        match self.unknown_2_0 {
+            Some(bytes) => bytes != SaveHeader::EXPECTED_UNKNOWN_2_0,
+            None => false,
+        }
+    }
 
-        let saving_replay = d.parse_bool()?;
+    /// Reads the save header out of a Factorio save zip.
+    ///
+    /// Saves contain a single root folder, usually named after the save, with `level-init.dat` inside it.
+    pub fn from_save_zip<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut archive = zip::ZipArchive::new(reader).map_err(|e| Error::NotZip(e.to_string()))?;
+
+        // Root folder name varies, so look for any level-init.dat at most one folder deep
+        let index = (0..archive.len())
+            .find(|&i| archive.name_for_index(i).is_some_and(is_level_init_path))
+            .ok_or(Error::SaveHeaderMissing)?;
+
+        // Only the start of the entry needs to be decompressed
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        SaveHeader::from_reader(entry)
+    }
 
-        let allow_non_admin_debug_options = d.parse_bool()?;
+    /// Reads the save header out of the Factorio save zip at `path`.
+    pub fn from_save_zip_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path).map_err(|e| Error::Io(e.to_string()))?;
+        SaveHeader::from_save_zip(file)
+    }
+}
 
-        let loaded_from = d.parse_version48()?;
+/// Map generation settings stored in `level-init.dat`, see `MapGenSettings` in the Lua API.
+///
+/// Autoplace settings, property expression names and 2.0 territory settings aren't
+/// decoded yet. Saves which set any of them have [`LevelInit::map_gen_settings`] left empty.
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MapGenSettings {
+    /// Only before 2.0
+    pub terrain_segmentation: Option<f32>,
+    /// Only before 2.0
+    pub water: Option<f32>,
+    /// Controls by name, e.g. `iron-ore`, in the order they are stored
+    pub autoplace_controls: Vec<(String, AutoplaceControl)>,
+    pub default_enable_all_autoplace_controls: bool,
+    pub seed: u32,
+    pub width: u32,
+    pub height: u32,
+    pub area_to_generate_at_start: BoundingBox,
+    /// 4 bytes with unknown meaning, `00 00 01 80` in every save seen so far
+    pub unknown: [u8; 4],
+    pub starting_area: f32,
+    pub peaceful_mode: bool,
+    /// Only in 2.0 onwards
+    pub no_enemies_mode: bool,
+    pub starting_points: Vec<MapPosition>,
+    pub cliff_settings: CliffSettings,
+}
 
-        let loaded_from_build = match factorio_version.main >= 2 {
-            true => BuildNumber::Build32(d.next_u32()?),
-            false => BuildNumber::Build16(d.next_u16()?),
-        };
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AutoplaceControl {
+    pub frequency: f32,
+    pub size: f32,
+    pub richness: f32,
+}
 
-        let allowed_commands = d.parse_bool()?;
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct CliffSettings {
+    /// Cliff prototype, empty when cliffs are disabled
+    pub name: String,
+    /// Autoplace control for the cliffs, only in 2.0 onwards
+    pub control: Option<String>,
+    pub cliff_elevation_0: f32,
+    pub cliff_elevation_interval: f32,
+    pub richness: f32,
+    /// Only in 2.0 onwards
+    pub cliff_smoothing: Option<f32>,
+}
 
-        // 2.0 seems to have introduced 4 new bytes here, not sure what they are
-        // All test samples seem to have these exact bytes:
-        //   00 00 A0 00
-        // Skip them for now
-        if factorio_version.main >= 2 {
-            for _ in 0..4 {
-                d.next_u8()?;
-            }
-        }
-
-        // Next is the number of mods attached to the save
-        let num_mods = d.next_u32_optim()?;
-        let mut mods = Vec::with_capacity(num_mods as usize);
-        // Iterate and build SaveHeaderMods
-        for _ in 0..num_mods {
-            mods.push(SaveHeaderMod {
-                name: d.parse_string_saveheader()?,
-                version: d.parse_version48()?,
-                crc: d.next_u32()?,
-            });
-        }
+/// Position in tiles. Stored as fixed point with 1/256 tile precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct MapPosition {
+    pub x: f64,
+    pub y: f64,
+}
 
-        Ok(SaveHeader {
-            factorio_version,
-            campaign,
-            name,
-            base_mod,
-            difficulty,
-            finished,
-            player_won,
-            next_level,
-            can_continue,
-            finished_but_continuing,
-            saving_replay,
-            allow_non_admin_debug_options,
-            loaded_from,
-            loaded_from_build,
-            allowed_commands,
-            mods,
-        })
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct BoundingBox {
+    pub left_top: MapPosition,
+    pub right_bottom: MapPosition,
+}
+
+pub(crate) fn is_level_init_path(name: &str) -> bool {
+    let mut components = name.rsplitn(3, '/');
+    matches!(
+        (components.next(), components.next(), components.next()),
+        (Some("level-init.dat"), _, None)
+    )
+}
+
+/// Full contents of `level-init.dat`, i.e. the [`SaveHeader`] and what follows it
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct LevelInit {
+    pub header: SaveHeader,
+    /// Appears to be a checksum of the startup settings, 0 when there are none
+    pub startup_settings_crc: u32,
+    /// Snapshot of the startup mod settings the save was created with,
+    /// in the same shape as [`ModSettings::startup`]
+    pub startup_settings: PropertyTree,
+    /// Undecoded bytes between the startup settings and the map generation settings.
+    /// 2.0 keeps the victory and defeat screen details here.
+    #[serde(default)]
+    pub before_map_gen_settings: Vec<u8>,
+    /// Map generation settings the save was created with. `None` before 1.1, whose
+    /// layout isn't known, or if they use parts of the layout that aren't decoded yet,
+    /// in which case they are left at the start of [`LevelInit::remainder`].
+    #[serde(default)]
+    pub map_gen_settings: Option<MapGenSettings>,
+    /// Undecoded bytes following the map generation settings, starting with the map settings
+    pub remainder: Vec<u8>,
+}
+
+impl LevelInit {
+    /// Parses `level-init.dat`. Everything after the map generation settings ends up in
+    /// [`LevelInit::remainder`], so `options.mode` makes no difference here.
+    pub fn parse(input: &[u8], options: &ParseOptions) -> Result<Parsed<Self>> {
+        LevelInit::from_reader_with_options(input, options)
+    }
+
+    /// Parses `level-init.dat` as it is read from `reader`
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        LevelInit::from_reader_with_options(reader, &ParseOptions::default())
+            .map(|parsed| parsed.value)
+    }
+
+    /// Parses `level-init.dat` as it is read from `reader`, see [`LevelInit::parse`]
+    pub fn from_reader_with_options<R: Read>(
+        reader: R,
+        options: &ParseOptions,
+    ) -> Result<Parsed<Self>> {
+        let mut d = Deserialiser::new(reader, options.limits.clone());
+        let level_init = d.parse_level_init().map_err(|e| d.context(e))?;
+        d.finish(level_init, options.mode)
+    }
+
+    /// Writes `level-init.dat` to `writer`
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        Serialiser::new(writer).write_level_init(self)
+    }
+
+    /// Serialises to the bytes of `level-init.dat`
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for LevelInit {
+    type Error = Error;
+
+    fn try_from(input: &[u8]) -> Result<Self> {
+        LevelInit::from_reader(input)
+    }
+}
+
+impl TryInto<Vec<u8>> for LevelInit {
+    type Error = Error;
+
+    fn try_into(self) -> Result<Vec<u8>> {
+        self.to_bytes()
     }
 }
 
@@ -244,30 +413,248 @@ impl Display for SaveHeaderMod {
     }
 }
 
-struct Deserialiser<'a> {
-    byte_slice: &'a [u8],
+/// Upper bound on capacity reserved up front for lists, dictionaries and mod lists
+const MAX_PREALLOCATED_ENTRIES: usize = 1024;
+
+/// Largest integer every smaller integer can be stored exactly as a double
+const MAX_EXACT_DOUBLE_INTEGER: u64 = 1 << 53;
+
+/// Parts of the format that depend on which game version a file is for.
+///
+/// Save header thresholds follow OpenFactorioServerManager's reader. Only the 1.1 and 2.0
+/// layouts have been checked against real saves, the older ones are untested.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Encoding {
+    /// Integer PropertyTree types, added in Factorio 2.0. Before that, integers are doubles
+    pub(crate) integer_types: bool,
+    /// Space-optimised string lengths, versions and mod count in save headers, from 0.14.14
+    optimised_ints: bool,
+    /// Unused byte after the save header version, after 0.17.0
+    header_unused_byte: bool,
+    /// `can_continue` and `finished_but_continuing` in save headers, after 0.12
+    can_continue: bool,
+    /// `allow_non_admin_debug_options` in save headers, after 0.16
+    allow_non_admin_debug_options: bool,
+    /// Mod CRCs in save headers, after 0.15.0.91
+    mod_crc: bool,
+    /// 32-bit `loaded_from_build` and the 4 unknown bytes in save headers, from 2.0
+    header_2_0: bool,
+    /// Known layout of the map generation settings in `level-init.dat`, from 1.1
+    map_gen_settings: bool,
+}
+
+impl Encoding {
+    pub(crate) fn for_version(version: &Version) -> Self {
+        let after =
+            |main, major, minor, developer| *version > Version::new(main, major, minor, developer);
+        let is_2_0 = *version >= Version::new(2, 0, 0, 0);
+        Encoding {
+            integer_types: is_2_0,
+            optimised_ints: *version >= Version::new(0, 14, 14, 0),
+            header_unused_byte: after(0, 17, 0, 0),
+            can_continue: after(0, 12, 0, 0),
+            allow_non_admin_debug_options: after(0, 16, 0, 0),
+            mod_crc: after(0, 15, 0, 91),
+            header_2_0: is_2_0,
+            map_gen_settings: *version >= Version::new(1, 1, 0, 0),
+        }
+    }
+}
+
+/// What `mod-settings.dat` starts with, depending on the version that wrote it
+enum ModSettingsStart {
+    Version(Version),
+    /// Before 0.17, the type byte of the settings dictionary came first
+    Legacy {
+        any_type: bool,
+    },
+}
+
+/// Reads the binary formats from any [`Read`], pulling bytes in as they are needed
+struct Deserialiser<R: Read> {
+    reader: R,
+    /// Number of bytes consumed so far
+    offset: usize,
+    /// Logical location of the value currently being parsed, used for error context
+    path: Vec<PathSegment>,
+    limits: ParseLimits,
+    /// Current PropertyTree nesting depth
+    depth: usize,
+    /// Approximate bytes allocated for parsed values so far
+    allocated: usize,
+    /// Decided by the file's version once it has been read
+    encoding: Encoding,
+}
+
+enum PathSegment {
+    Field(&'static str),
+    Key(String),
+    Index(usize),
 }
 
-impl<'a> Deserialiser<'a> {
-    fn peek_u8(&mut self) -> Result<u8> {
-        match self.byte_slice.bytes().next() {
-            None => Err(Error::Eof),
-            Some(r) => r.map_err(|e| Error::Message(format!("{:?}", e))),
+impl<R: Read> Deserialiser<R> {
+    fn new(reader: R, limits: ParseLimits) -> Self {
+        Deserialiser {
+            reader,
+            offset: 0,
+            path: Vec::new(),
+            limits,
+            depth: 0,
+            allocated: 0,
+            encoding: Encoding::default(),
+        }
+    }
+
+    fn finish<T>(&mut self, value: T, mode: ParseMode) -> Result<Parsed<T>> {
+        let offset = self.offset;
+        match mode {
+            ParseMode::Strict => {
+                // Should be at EOF now
+                if let Err(Error::Eof) = self.next_u8() {
+                    Ok(Parsed { value, offset })
+                } else {
+                    self.offset = offset;
+                    Err(self.context(Error::TrailingBytes))
+                }
+            }
+            ParseMode::Lenient => Ok(Parsed { value, offset }),
+        }
+    }
+
+    /// Attaches the current offset and path to an error
+    fn context(&self, error: Error) -> Error {
+        if let Error::Context { .. } = error {
+            return error;
+        }
+
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => write!(path, ".{}", name).unwrap(),
+                PathSegment::Key(key) if path.is_empty() => path.push_str(key),
+                PathSegment::Key(key) => write!(path, "/{}", key).unwrap(),
+                PathSegment::Index(i) => write!(path, "[{}]", i).unwrap(),
+            }
+        }
+
+        Error::Context {
+            offset: self.offset,
+            path,
+            source: Box::new(error),
+        }
+    }
+
+    /// Runs `f` with `segment` appended to the path.
+    /// On error the path is left as-is so [`Deserialiser::context`] can report it.
+    fn with_segment<T>(
+        &mut self,
+        segment: PathSegment,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.path.push(segment);
+        let value = f(self)?;
+        self.path.pop();
+        Ok(value)
+    }
+
+    fn field<T>(
+        &mut self,
+        name: &'static str,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        self.with_segment(PathSegment::Field(name), f)
+    }
+
+    /// Field only present in some versions, read as its default when `present` is false
+    fn optional_field<T: Default>(
+        &mut self,
+        present: bool,
+        name: &'static str,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        if present {
+            self.field(name, f)
+        } else {
+            Ok(T::default())
+        }
+    }
+
+    fn key<T>(&mut self, key: &str, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.with_segment(PathSegment::Key(key.to_owned()), f)
+    }
+
+    fn index<T>(&mut self, i: usize, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.with_segment(PathSegment::Index(i), f)
+    }
+
+    /// Reads exactly `N` bytes, failing with [`Error::Eof`] if the input ends first
+    fn next_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0; N];
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof => Error::Eof,
+                _ => Error::Io(e.to_string()),
+            })?;
+        self.offset += N;
+        Ok(buf)
+    }
+
+    /// Reads exactly `len` bytes without counting them towards the offset yet,
+    /// so that errors in their contents can be reported at their start.
+    /// Memory grows with what's actually read rather than with `len`.
+    fn read_vec(&mut self, len: usize) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        if buf.len() < len {
+            Err(Error::Eof)
+        } else {
+            Ok(buf)
         }
     }
 
     fn next_u8(&mut self) -> Result<u8> {
-        let b = self.peek_u8()?;
-        self.byte_slice = &self.byte_slice[1..];
+        let [b] = self.next_bytes()?;
         Ok(b)
     }
 
+    /// Fails if a list, dictionary or mod list declares more entries than allowed
+    fn check_entries(&self, len: u32) -> Result<()> {
+        if len as usize > self.limits.max_entries {
+            Err(Error::LimitExceeded(format!(
+                "{} entries, more than {}",
+                len, self.limits.max_entries
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Accounts for `bytes` of memory about to be allocated for parsed values
+    fn allocate(&mut self, bytes: usize) -> Result<()> {
+        self.allocated = self.allocated.saturating_add(bytes);
+        if self.allocated > self.limits.max_total_alloc {
+            Err(Error::LimitExceeded(format!(
+                "Total allocation larger than {} bytes",
+                self.limits.max_total_alloc
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Capacity to reserve for `len` elements read from the input.
+    /// The count hasn't been checked against the data yet, so don't trust it too far.
+    fn capacity_for(&self, len: u32) -> usize {
+        (len as usize).min(MAX_PREALLOCATED_ENTRIES)
+    }
+
     fn next_u16(&mut self) -> Result<u16> {
-        let next_slice: &[u8; 2] = &self.byte_slice[0..2]
-            .try_into()
-            .map_err(|_| Error::ByteSlicingError)?;
-        self.byte_slice = &self.byte_slice[2..];
-        Ok(u16::from_le_bytes(*next_slice))
+        Ok(u16::from_le_bytes(self.next_bytes()?))
     }
 
     fn next_u16_optim(&mut self) -> Result<u16> {
@@ -279,12 +666,26 @@ impl<'a> Deserialiser<'a> {
         }
     }
 
+    /// Space-optimised in save headers from 0.14.14, a plain u16 before
+    fn next_u16_header(&mut self) -> Result<u16> {
+        if self.encoding.optimised_ints {
+            self.next_u16_optim()
+        } else {
+            self.next_u16()
+        }
+    }
+
     fn next_u32(&mut self) -> Result<u32> {
-        let next_slice: &[u8; 4] = &self.byte_slice[0..4]
-            .try_into()
-            .map_err(|_| Error::ByteSlicingError)?;
-        self.byte_slice = &self.byte_slice[4..];
-        Ok(u32::from_le_bytes(*next_slice))
+        Ok(u32::from_le_bytes(self.next_bytes()?))
+    }
+
+    /// Space-optimised in save headers from 0.14.14, a plain u32 before
+    fn next_u32_header(&mut self) -> Result<u32> {
+        if self.encoding.optimised_ints {
+            self.next_u32_optim()
+        } else {
+            self.next_u32()
+        }
     }
 
     fn next_u32_optim(&mut self) -> Result<u32> {
@@ -304,12 +705,25 @@ impl<'a> Deserialiser<'a> {
         Ok(b != 0)
     }
 
+    /// Save header flags are written back as 0 or 1, so anything else is rejected
+    /// rather than being silently changed
+    fn parse_bool_header(&mut self) -> Result<bool> {
+        match self.next_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(Error::Syntax(format!(
+                "Expected a bool of 0 or 1, got {}",
+                b
+            ))),
+        }
+    }
+
     fn parse_double(&mut self) -> Result<f64> {
-        let next_slice: &[u8; 8] = &self.byte_slice[0..8]
-            .try_into()
-            .map_err(|_| Error::ByteSlicingError)?;
-        self.byte_slice = &self.byte_slice[8..];
-        Ok(f64::from_le_bytes(*next_slice))
+        Ok(f64::from_le_bytes(self.next_bytes()?))
+    }
+
+    fn parse_float(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.next_bytes()?))
     }
 
     fn parse_string(&mut self) -> Result<String> {
@@ -322,20 +736,29 @@ impl<'a> Deserialiser<'a> {
 
     fn _parse_string(&mut self, has_empty_indicator: bool) -> Result<String> {
         // in mod-settings dat, there is an extra byte indicating if the string is empty?
+        // Strings are always written back with it unset, see PropertyTree::String
         if has_empty_indicator && self.parse_bool()? {
             Ok(String::new())
         } else {
-            let len = self.next_u32_optim()?;
+            // PropertyTrees came after 0.14.14, so their lengths are always space-optimised
+            let len = if has_empty_indicator {
+                self.next_u32_optim()?
+            } else {
+                self.next_u32_header()?
+            };
 
             // Read `len` bytes representing UTF-8 string
             let len = len as usize;
-            let next_slice = self.byte_slice[0..len]
-                .try_into()
-                .map_err(|_| Error::ByteSlicingError)?;
-            let utf8 = std::str::from_utf8(next_slice)
-                .map_err(|e| Error::Utf8(e))?
-                .to_string();
-            self.byte_slice = &self.byte_slice[len..];
+            if len > self.limits.max_string_len {
+                return Err(Error::LimitExceeded(format!(
+                    "String of {} bytes longer than {}",
+                    len, self.limits.max_string_len
+                )));
+            }
+            self.allocate(len)?;
+            let bytes = self.read_vec(len)?;
+            let utf8 = String::from_utf8(bytes).map_err(|e| Error::Utf8(e.utf8_error()))?;
+            self.offset += len;
 
             Ok(utf8)
         }
@@ -343,6 +766,10 @@ impl<'a> Deserialiser<'a> {
 
     fn parse_version(&mut self) -> Result<Version> {
         let main = self.next_u16()?;
+        self.parse_version_after_main(main)
+    }
+
+    fn parse_version_after_main(&mut self, main: u16) -> Result<Version> {
         let major = self.next_u16()?;
         let minor = self.next_u16()?;
         let developer = self.next_u16()?;
@@ -356,101 +783,622 @@ impl<'a> Deserialiser<'a> {
     }
 
     fn parse_version48(&mut self) -> Result<Version48> {
-        let main = self.next_u16_optim()?;
-        let major = self.next_u16_optim()?;
-        let minor = self.next_u16_optim()?;
+        let main = self.next_u16_header()?;
+        let major = self.next_u16_header()?;
+        let minor = self.next_u16_header()?;
 
-        Ok(Version48 {
-            main,
-            major,
-            minor,
-        })
+        Ok(Version48 { main, major, minor })
     }
 
-    fn parse_property_tree(&mut self) -> Result<PropertyTree> {
-        // One byte representing the PropertyTreeType
-        let type_u8 = self.next_u8()?;
-
-        // One bool "not important outside of Factorio internals"
-        self.parse_bool()?;
-
-        match type_u8.try_into()? {
-            PropertyTreeType::None => {
-                // Nothing
-                Ok(PropertyTree::None)
-            }
-            PropertyTreeType::Bool => {
-                // 1 bool
-                Ok(PropertyTree::Bool(self.parse_bool()?))
-            }
-            PropertyTreeType::Number => {
-                // 1 double
-                Ok(PropertyTree::Number(self.parse_double()?))
-            }
-            PropertyTreeType::String => {
-                // 1 string
-                Ok(PropertyTree::String(self.parse_string()?))
-            }
-            PropertyTreeType::List => {
-                // 1 u32 representing the number of elements
-                let len = self.next_u32()?;
-
-                // Iterate over list items
-                let mut list = Vec::with_capacity(len as usize);
-                for _ in 0..len {
-                    // 1 string, unused
-                    self.parse_string()?;
+    fn parse_mod_settings_start(&mut self) -> Result<ModSettingsStart> {
+        // Files from before 0.17 start straight away with the settings PropertyTree.
+        // Its type byte and any-type flag would read as a main version of 5 or
+        // 261, which no release has, so use them to tell the layouts apart
+        let start: [u8; 2] = self.field("version", Self::next_bytes)?;
+        if start[0] == u8::try_from(PropertyTreeType::Dictionary)? && start[1] <= 1 {
+            return Ok(ModSettingsStart::Legacy {
+                any_type: start[1] != 0,
+            });
+        }
 
-                    // 1 property tree
-                    list.push(self.parse_property_tree()?);
-                }
+        // Otherwise it's 8 bytes representing game version
+        let main = u16::from_le_bytes(start);
+        let version = self.field("version", |d| d.parse_version_after_main(main))?;
+        Ok(ModSettingsStart::Version(version))
+    }
 
-                Ok(PropertyTree::List(list))
+    fn parse_mod_settings(&mut self) -> Result<ModSettings> {
+        let (version, tree) = match self.parse_mod_settings_start()? {
+            ModSettingsStart::Legacy { any_type } => {
+                let dictionary = u8::try_from(PropertyTreeType::Dictionary)?;
+                (
+                    None,
+                    self.parse_property_tree_of_type(dictionary, any_type)?,
+                )
             }
-            PropertyTreeType::Dictionary => {
-                // 1 u32 representing the number of elements
-                let len = self.next_u32()?;
-
-                // Iterate over dict items
-                let mut dict = Vec::with_capacity(len as usize);
-                for _ in 0..len {
-                    // 1 string representing the key
-                    let key = self.parse_string()?;
-
-                    // 1 property tree
-                    let value = self.parse_property_tree()?;
-
-                    dict.push((key, value));
+            ModSettingsStart::Version(version) => {
+                self.encoding = Encoding::for_version(&version);
+
+                // Next is a single byte always set to false (not 1)
+                let false_sentinel = self.field("sentinel", Self::parse_bool)?;
+                if false_sentinel {
+                    return Err(Error::Syntax(
+                        "After-version sentinel expected to be false, got true".to_owned(),
+                    ));
                 }
 
-                Ok(PropertyTree::Dictionary(dict))
+                (Some(version), self.parse_property_tree()?)
             }
-        }
-    }
-}
-
-struct Serialiser {
-    bytes: Vec<u8>,
-}
+        };
 
-impl Serialiser {
-    fn new() -> Self {
-        Serialiser { bytes: Vec::new() }
-    }
+        // Then is a dictionary-type PropertyTree with empty key
+        // This contains the three settings sections
+        let any_type = tree.is_any_type();
+        let startup;
+        let runtime_global;
+        let runtime_per_user;
+        match tree.into_inner() {
+            PropertyTree::Dictionary(dict) => {
+                let mut dict: HashMap<String, PropertyTree> = dict.into_iter().collect();
+                match dict.remove("startup") {
+                    None => {
+                        return Err(Error::Syntax(
+                            "Settings section 'startup' missing".to_owned(),
+                        ))
+                    }
+                    Some(section) => startup = section,
+                };
+                match dict.remove("runtime-global") {
+                    None => {
+                        return Err(Error::Syntax(
+                            "Settings section 'runtime-global' missing".to_owned(),
+                        ))
+                    }
+                    Some(section) => runtime_global = section,
+                };
+                match dict.remove("runtime-per-user") {
+                    None => {
+                        return Err(Error::Syntax(
+                            "Settings section 'runtime-per-user' missing".to_owned(),
+                        ))
+                    }
+                    Some(section) => runtime_per_user = section,
+                };
+            }
+            _ => {
+                return Err(Error::Syntax(
+                    "Top-level PropertyTree not dictionary type".to_owned(),
+                ))
+            }
+        }
+
+        Ok(ModSettings {
+            version,
+            any_type,
+            startup,
+            runtime_global,
+            runtime_per_user,
+        })
+    }
+
+    fn parse_save_header(&mut self) -> Result<SaveHeader> {
+        let mut header = self.parse_save_header_summary()?;
+        let layout = self.encoding;
+
+        header.mods = self.field("mods", |d| {
+            // Next is the number of mods attached to the save
+            let num_mods = d.next_u32_header()?;
+            d.check_entries(num_mods)?;
+            let mut mods = Vec::with_capacity(d.capacity_for(num_mods));
+            // Iterate and build SaveHeaderMods
+            for i in 0..num_mods {
+                d.allocate(size_of::<SaveHeaderMod>())?;
+                mods.push(d.index(i as usize, |d| {
+                    Ok(SaveHeaderMod {
+                        name: d.field("name", Self::parse_string_saveheader)?,
+                        version: d.field("version", Self::parse_version48)?,
+                        crc: d.optional_field(layout.mod_crc, "crc", Self::next_u32)?,
+                    })
+                })?);
+            }
+            Ok(mods)
+        })?;
+
+        Ok(header)
+    }
+
+    /// Everything in the save header up to the mod list, which is left empty
+    fn parse_save_header_summary(&mut self) -> Result<SaveHeader> {
+        // First is 8 bytes representing game version, which decides the rest of the layout
+        let factorio_version = self.field("factorio_version", Self::parse_version)?;
+        self.encoding = Encoding::for_version(&factorio_version);
+        let layout = self.encoding;
+
+        // Next is a single unused byte, always written as 0, so anything else is rejected
+        self.optional_field(layout.header_unused_byte, "_", |d| match d.next_u8()? {
+            0 => Ok(()),
+            b => Err(Error::Syntax(format!(
+                "Unused byte expected to be 0, got {}",
+                b
+            ))),
+        })?;
+
+        let campaign = self.field("campaign", Self::parse_string_saveheader)?;
+
+        let name = self.field("name", Self::parse_string_saveheader)?;
+
+        let base_mod = self.field("base_mod", Self::parse_string_saveheader)?;
+
+        // Next is a number representing difficulty
+        let difficulty = self.field("difficulty", |d| d.next_u8().map(Difficulty::from))?;
+
+        let finished = self.field("finished", Self::parse_bool_header)?;
+
+        let player_won = self.field("player_won", Self::parse_bool_header)?;
+
+        let next_level = self.field("next_level", Self::parse_string_saveheader)?;
+
+        let can_continue =
+            self.optional_field(layout.can_continue, "can_continue", Self::parse_bool_header)?;
+
+        let finished_but_continuing = self.optional_field(
+            layout.can_continue,
+            "finished_but_continuing",
+            Self::parse_bool_header,
+        )?;
+
+        let saving_replay = self.field("saving_replay", Self::parse_bool_header)?;
+
+        let allow_non_admin_debug_options = self.optional_field(
+            layout.allow_non_admin_debug_options,
+            "allow_non_admin_debug_options",
+            Self::parse_bool_header,
+        )?;
+
+        let loaded_from = self.field("loaded_from", Self::parse_version48)?;
+
+        let loaded_from_build = self.field("loaded_from_build", |d| match layout.header_2_0 {
+            true => Ok(BuildNumber::Build32(d.next_u32()?)),
+            false => Ok(BuildNumber::Build16(d.next_u16()?)),
+        })?;
+
+        let allowed_commands = self.field("allowed_commands", |d| {
+            d.next_u8().map(AllowedCommands::from)
+        })?;
+
+        // 2.0 seems to have introduced 4 new bytes here, not sure what they are
+        // Keep them as-is so they survive a round trip
+        let unknown_2_0 = self.optional_field(layout.header_2_0, "unknown_2_0", |d| {
+            Ok(Some([
+                d.next_u8()?,
+                d.next_u8()?,
+                d.next_u8()?,
+                d.next_u8()?,
+            ]))
+        })?;
+
+        Ok(SaveHeader {
+            factorio_version,
+            campaign,
+            name,
+            base_mod,
+            difficulty,
+            finished,
+            player_won,
+            next_level,
+            can_continue,
+            finished_but_continuing,
+            saving_replay,
+            allow_non_admin_debug_options,
+            loaded_from,
+            loaded_from_build,
+            allowed_commands,
+            unknown_2_0,
+            mods: Vec::new(),
+        })
+    }
+
+    fn parse_level_init(&mut self) -> Result<LevelInit> {
+        // Header first, which also decides the encoding, then the embedded startup settings
+        let header = self.field("header", Self::parse_save_header)?;
+        let startup_settings_crc = self.field("startup_settings_crc", Self::next_u32)?;
+        let startup_settings = self.field("startup_settings", Self::parse_property_tree)?;
+
+        // Everything else is kept as-is
+        let mut remainder = Vec::new();
+        (&mut self.reader)
+            .take(
+                (self.limits.max_total_alloc.saturating_sub(self.allocated) as u64)
+                    .saturating_add(1),
+            )
+            .read_to_end(&mut remainder)
+            .map_err(|e| Error::Io(e.to_string()))?;
+        self.allocate(remainder.len())?;
+        self.offset += remainder.len();
+
+        // Then pick out the map generation settings, if they can be decoded
+        let (before_map_gen_settings, map_gen_settings, remainder) =
+            match self.split_map_gen_settings(&remainder) {
+                Some((before, settings, len)) => (
+                    remainder[..before].to_vec(),
+                    Some(settings),
+                    remainder[len..].to_vec(),
+                ),
+                None => (Vec::new(), None, remainder),
+            };
+
+        Ok(LevelInit {
+            header,
+            startup_settings_crc,
+            startup_settings,
+            before_map_gen_settings,
+            map_gen_settings,
+            remainder,
+        })
+    }
+
+    /// Decodes the map generation settings near the start of `rest`, returning where they
+    /// start and end. Nothing after them is needed, so anything unexpected gives `None`
+    /// and leaves `rest` undecoded rather than failing.
+    fn split_map_gen_settings(&self, rest: &[u8]) -> Option<(usize, MapGenSettings, usize)> {
+        if !self.encoding.map_gen_settings {
+            return None;
+        }
+
+        let mut d = Deserialiser::new(rest, self.limits.clone());
+        d.encoding = self.encoding;
+        d.skip_before_map_gen_settings().ok()?;
+        let start = d.offset;
+        let settings = d.parse_map_gen_settings().ok()?;
+        Some((start, settings, d.offset))
+    }
+
+    fn skip_before_map_gen_settings(&mut self) -> Result<()> {
+        // 2.0 starts with the victory and defeat screen details
+        if self.encoding.header_2_0 {
+            self.skip_ending_info()?;
+            self.skip_ending_info()?;
+        }
+
+        // Followed by bytes of unknown meaning, checked in case they are really counts
+        let unknown = if self.encoding.header_2_0 { 25 } else { 12 };
+        for _ in 0..unknown {
+            if self.next_u8()? != 0 {
+                return Err(Error::Syntax(
+                    "Unexpected bytes before map generation settings".to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Title, message, bullet points, final message and image of a victory or defeat screen
+    fn skip_ending_info(&mut self) -> Result<()> {
+        self.skip_localised_string()?;
+        self.skip_localised_string()?;
+        let bullet_points = self.next_u32_header()?;
+        self.check_entries(bullet_points)?;
+        for _ in 0..bullet_points {
+            self.skip_localised_string()?;
+        }
+        self.skip_localised_string()?;
+        self.parse_string_saveheader()?;
+        Ok(())
+    }
+
+    fn skip_localised_string(&mut self) -> Result<()> {
+        // Empty, or a key followed by parameters which are localised strings themselves
+        match self.next_u8()? {
+            0 => Ok(()),
+            1 => {
+                if self.depth >= self.limits.max_depth {
+                    return Err(Error::LimitExceeded(format!(
+                        "Localised string nested deeper than {}",
+                        self.limits.max_depth
+                    )));
+                }
+
+                self.parse_string_saveheader()?;
+                let parameters = self.next_u32_header()?;
+                self.check_entries(parameters)?;
+                self.depth += 1;
+                for _ in 0..parameters {
+                    self.skip_localised_string()?;
+                }
+                self.depth -= 1;
+                Ok(())
+            }
+            b => Err(Error::Syntax(format!(
+                "Unknown localised string kind {}",
+                b
+            ))),
+        }
+    }
+
+    fn parse_map_gen_settings(&mut self) -> Result<MapGenSettings> {
+        let layout = self.encoding;
+        let before_2_0 = !layout.header_2_0;
+
+        let terrain_segmentation =
+            self.optional_field(before_2_0, "terrain_segmentation", |d| {
+                d.parse_float().map(Some)
+            })?;
+        let water = self.optional_field(before_2_0, "water", |d| d.parse_float().map(Some))?;
+
+        let autoplace_controls = self.field("autoplace_controls", |d| {
+            let len = d.next_u32_header()?;
+            d.check_entries(len)?;
+            let mut controls = Vec::with_capacity(d.capacity_for(len));
+            for i in 0..len {
+                d.allocate(size_of::<(String, AutoplaceControl)>())?;
+                controls.push(d.index(i as usize, |d| {
+                    let name = d.field("name", Self::parse_string_saveheader)?;
+                    let control = AutoplaceControl {
+                        frequency: d.field("frequency", Self::parse_float)?,
+                        size: d.field("size", Self::parse_float)?,
+                        richness: d.field("richness", Self::parse_float)?,
+                    };
+                    Ok((name, control))
+                })?);
+            }
+            Ok(controls)
+        })?;
+
+        self.field("autoplace_settings", Self::expect_empty)?;
+
+        let default_enable_all_autoplace_controls = self.field(
+            "default_enable_all_autoplace_controls",
+            Self::parse_bool_header,
+        )?;
+        let seed = self.field("seed", Self::next_u32)?;
+        let width = self.field("width", Self::next_u32)?;
+        let height = self.field("height", Self::next_u32)?;
+        let area_to_generate_at_start = self.field("area_to_generate_at_start", |d| {
+            Ok(BoundingBox {
+                left_top: d.field("left_top", Self::parse_map_position)?,
+                right_bottom: d.field("right_bottom", Self::parse_map_position)?,
+            })
+        })?;
+        let unknown = self.field("unknown", Self::next_bytes)?;
+        let starting_area = self.field("starting_area", Self::parse_float)?;
+        let peaceful_mode = self.field("peaceful_mode", Self::parse_bool_header)?;
+        let no_enemies_mode = self.optional_field(
+            layout.header_2_0,
+            "no_enemies_mode",
+            Self::parse_bool_header,
+        )?;
+
+        let starting_points = self.field("starting_points", |d| {
+            let len = d.next_u32_header()?;
+            d.check_entries(len)?;
+            let mut points = Vec::with_capacity(d.capacity_for(len));
+            for i in 0..len {
+                d.allocate(size_of::<MapPosition>())?;
+                points.push(d.index(i as usize, Self::parse_map_position)?);
+            }
+            Ok(points)
+        })?;
+
+        self.field("property_expression_names", Self::expect_empty)?;
+
+        let cliff_settings = self.field("cliff_settings", |d| {
+            Ok(CliffSettings {
+                name: d.field("name", Self::parse_string_saveheader)?,
+                control: d.optional_field(layout.header_2_0, "control", |d| {
+                    d.parse_string_saveheader().map(Some)
+                })?,
+                cliff_elevation_0: d.field("cliff_elevation_0", Self::parse_float)?,
+                cliff_elevation_interval: d.field("cliff_elevation_interval", Self::parse_float)?,
+                richness: d.field("richness", Self::parse_float)?,
+                cliff_smoothing: d.optional_field(layout.header_2_0, "cliff_smoothing", |d| {
+                    d.parse_float().map(Some)
+                })?,
+            })
+        })?;
+
+        // Thought to be whether there are territory settings, which aren't decoded yet
+        if layout.header_2_0 {
+            self.field("territory_settings", |d| match d.parse_bool_header()? {
+                false => Ok(()),
+                true => Err(Error::Syntax("Territory settings not supported".to_owned())),
+            })?;
+        }
+
+        Ok(MapGenSettings {
+            terrain_segmentation,
+            water,
+            autoplace_controls,
+            default_enable_all_autoplace_controls,
+            seed,
+            width,
+            height,
+            area_to_generate_at_start,
+            unknown,
+            starting_area,
+            peaceful_mode,
+            no_enemies_mode,
+            starting_points,
+            cliff_settings,
+        })
+    }
+
+    /// For collections whose items aren't decoded yet, so can only be read when empty
+    fn expect_empty(&mut self) -> Result<()> {
+        match self.next_u32_header()? {
+            0 => Ok(()),
+            len => Err(Error::Syntax(format!(
+                "{} entries, but only empty is supported",
+                len
+            ))),
+        }
+    }
+
+    fn parse_map_position(&mut self) -> Result<MapPosition> {
+        // Positions can also be stored relative to the previous one, which isn't supported.
+        // Absolute ones are marked by i16::MAX, followed by fixed point coordinates
+        let marker = i16::from_le_bytes(self.next_bytes()?);
+        if marker != i16::MAX {
+            return Err(Error::Syntax(
+                "Relative map positions not supported".to_owned(),
+            ));
+        }
+        let x = i32::from_le_bytes(self.next_bytes()?);
+        let y = i32::from_le_bytes(self.next_bytes()?);
+        Ok(MapPosition {
+            x: x as f64 / 256.0,
+            y: y as f64 / 256.0,
+        })
+    }
+
+    fn parse_property_tree(&mut self) -> Result<PropertyTree> {
+        // One byte representing the PropertyTreeType
+        let type_u8 = self.next_u8()?;
+
+        // One bool "not important outside of Factorio internals"
+        let any_type = self.parse_bool()?;
+
+        self.parse_property_tree_of_type(type_u8, any_type)
+    }
+
+    fn parse_property_tree_of_type(&mut self, type_u8: u8, any_type: bool) -> Result<PropertyTree> {
+        // Each level of nesting recurses, so bound it before going any deeper
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::LimitExceeded(format!(
+                "PropertyTree nested deeper than {}",
+                self.limits.max_depth
+            )));
+        }
+
+        self.depth += 1;
+        let tree = self.parse_property_tree_node(type_u8, any_type)?;
+        self.depth -= 1;
+        Ok(tree)
+    }
+
+    fn parse_property_tree_node(&mut self, type_u8: u8, any_type: bool) -> Result<PropertyTree> {
+        let tree = match type_u8.try_into()? {
+            PropertyTreeType::None => {
+                // Nothing
+                PropertyTree::None
+            }
+            PropertyTreeType::Bool => {
+                // 1 bool
+                PropertyTree::Bool(self.parse_bool()?)
+            }
+            PropertyTreeType::Number => {
+                // 1 double
+                PropertyTree::Number(self.parse_double()?)
+            }
+            PropertyTreeType::String => {
+                // 1 string
+                PropertyTree::String(self.parse_string()?)
+            }
+            PropertyTreeType::SignedInteger | PropertyTreeType::UnsignedInteger
+                if !self.encoding.integer_types =>
+            {
+                return Err(Error::Syntax(format!(
+                    "PropertyTree type {} not supported before Factorio 2.0",
+                    type_u8
+                )));
+            }
+            PropertyTreeType::SignedInteger => {
+                // 1 signed 64-bit int
+                PropertyTree::SignedInteger(i64::from_le_bytes(self.next_bytes()?))
+            }
+            PropertyTreeType::UnsignedInteger => {
+                // 1 unsigned 64-bit int
+                PropertyTree::UnsignedInteger(u64::from_le_bytes(self.next_bytes()?))
+            }
+            PropertyTreeType::List => {
+                // 1 u32 representing the number of elements
+                let len = self.next_u32()?;
+                self.check_entries(len)?;
+
+                // Iterate over list items
+                let mut list = Vec::with_capacity(self.capacity_for(len));
+                for i in 0..len {
+                    self.allocate(size_of::<(String, PropertyTree)>())?;
+                    self.index(i as usize, |d| {
+                        // 1 string, usually empty
+                        let key = d.parse_string()?;
+
+                        // 1 property tree
+                        list.push((key, d.parse_property_tree()?));
+                        Ok(())
+                    })?;
+                }
+
+                if list.iter().all(|(k, _)| k.is_empty()) {
+                    PropertyTree::List(list.into_iter().map(|(_, v)| v).collect())
+                } else {
+                    PropertyTree::KeyedList(list)
+                }
+            }
+            PropertyTreeType::Dictionary => {
+                // 1 u32 representing the number of elements
+                let len = self.next_u32()?;
+                self.check_entries(len)?;
+
+                // Iterate over dict items
+                let mut dict = Vec::with_capacity(self.capacity_for(len));
+                for i in 0..len {
+                    self.allocate(size_of::<(String, PropertyTree)>())?;
+
+                    // 1 string representing the key
+                    let key = self.index(i as usize, Self::parse_string)?;
+
+                    // 1 property tree
+                    let value = self.key(&key, Self::parse_property_tree)?;
+
+                    dict.push((key, value));
+                }
+
+                PropertyTree::Dictionary(dict)
+            }
+        };
+
+        if any_type {
+            Ok(PropertyTree::AnyType(Box::new(tree)))
+        } else {
+            Ok(tree)
+        }
+    }
+}
+
+/// Writes the binary formats to any [`Write`]
+struct Serialiser<W: Write> {
+    writer: W,
+    /// Decided by the version of the file being written
+    encoding: Encoding,
+}
 
-    fn write_u8(&mut self, value: u8) {
-        self.bytes.push(value)
+impl<W: Write> Serialiser<W> {
+    fn new(writer: W) -> Self {
+        Serialiser {
+            writer,
+            encoding: Encoding::default(),
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.writer
+            .write_all(bytes)
+            .map_err(|e| Error::Io(e.to_string()))
+    }
+
+    fn write_u8(&mut self, value: u8) -> Result<()> {
+        self.write_bytes(&[value])
     }
 
-    fn write_u16(&mut self, value: u16) {
-        self.bytes.extend(value.to_le_bytes().iter())
+    fn write_u16(&mut self, value: u16) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
     }
 
-    fn write_u32(&mut self, value: u32) {
-        self.bytes.extend(value.to_le_bytes().iter())
+    fn write_u32(&mut self, value: u32) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
     }
 
-    fn write_bool(&mut self, value: bool) {
+    fn write_bool(&mut self, value: bool) -> Result<()> {
         let byte = match value {
             true => 1,
             false => 0,
@@ -458,133 +1406,754 @@ impl Serialiser {
         self.write_u8(byte)
     }
 
-    fn write_double(&mut self, value: f64) {
-        self.bytes.extend(value.to_le_bytes().iter())
+    fn write_double(&mut self, value: f64) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
+    }
+
+    fn write_float(&mut self, value: f32) -> Result<()> {
+        self.write_bytes(&value.to_le_bytes())
     }
 
-    fn write_version(&mut self, version: u64) {
+    fn write_version(&mut self, version: u64) -> Result<()> {
         let main_version = (version >> 48) as u16;
-        self.write_u16(main_version);
+        self.write_u16(main_version)?;
         let major_version = (version >> 32) as u16;
-        self.write_u16(major_version);
+        self.write_u16(major_version)?;
         let minor_version = (version >> 16) as u16;
-        self.write_u16(minor_version);
+        self.write_u16(minor_version)?;
         let developer_version = version as u16;
-        self.write_u16(developer_version);
+        self.write_u16(developer_version)
+    }
+
+    fn write_u16_optim(&mut self, value: u16) -> Result<()> {
+        if value < 255 {
+            // If the value < 255 then write the value as a u8
+            self.write_u8(value as u8)?;
+        } else {
+            // Otherwise write a single byte with value 255, then write our full u16
+            self.write_u8(255)?;
+            self.write_u16(value)?;
+        }
+        Ok(())
     }
 
-    fn write_string(&mut self, value: String) {
-        // 1 bool indicating if the string is empty
-        if value.is_empty() {
-            self.write_bool(true);
+    fn write_u32_optim(&mut self, value: u32) -> Result<()> {
+        if value < 255 {
+            // If the value < 255 then write the value as a u8
+            self.write_u8(value as u8)?;
         } else {
-            self.write_bool(false);
+            // Otherwise write a single byte with value 255, then write our full u32
+            self.write_u8(255)?;
+            self.write_u32(value)?;
+        }
+        Ok(())
+    }
 
-            // Space-optimised unsigned int representing string length
-            if value.len() < 255 {
-                // If the value < 255 then write the value as a u8
-                self.write_u8(value.len() as u8);
-            } else {
-                // Otherwise write a single byte with value 255, then write our full u32
-                self.write_u8(255);
-                self.write_u32(value.len() as u32); // assuming usize fits into u32
+    /// Space-optimised in save headers from 0.14.14, a plain u16 before
+    fn write_u16_header(&mut self, value: u16) -> Result<()> {
+        if self.encoding.optimised_ints {
+            self.write_u16_optim(value)
+        } else {
+            self.write_u16(value)
+        }
+    }
+
+    /// Space-optimised in save headers from 0.14.14, a plain u32 before
+    fn write_u32_header(&mut self, value: u32) -> Result<()> {
+        if self.encoding.optimised_ints {
+            self.write_u32_optim(value)
+        } else {
+            self.write_u32(value)
+        }
+    }
+
+    fn write_version48(&mut self, version: &Version48) -> Result<()> {
+        self.write_u16_header(version.main)?;
+        self.write_u16_header(version.major)?;
+        self.write_u16_header(version.minor)
+    }
+
+    /// Writes a field only present in some versions, which has to be left at
+    /// its default when `present` is false
+    fn write_optional_field<T: Default + PartialEq + Debug>(
+        &mut self,
+        present: bool,
+        name: &str,
+        value: &T,
+        f: impl FnOnce(&mut Self, &T) -> Result<()>,
+    ) -> Result<()> {
+        if present {
+            f(self, value)
+        } else if *value == T::default() {
+            Ok(())
+        } else {
+            Err(Error::Syntax(format!(
+                "Field '{}' is {:?}, but not present in this game version",
+                name, value
+            )))
+        }
+    }
+
+    fn write_string(&mut self, value: &str) -> Result<()> {
+        self._write_string(value, true)
+    }
+
+    fn write_string_saveheader(&mut self, value: &str) -> Result<()> {
+        self._write_string(value, false)
+    }
+
+    fn _write_string(&mut self, value: &str, has_empty_indicator: bool) -> Result<()> {
+        // in mod-settings dat, 1 bool indicating if the string is empty
+        // The game leaves it unset even for empty strings, writing a zero length instead
+        if has_empty_indicator {
+            self.write_bool(false)?;
+        }
+
+        // Unsigned int representing string length, always space-optimised in PropertyTrees
+        // assuming usize fits into u32
+        if has_empty_indicator {
+            self.write_u32_optim(value.len() as u32)?;
+        } else {
+            self.write_u32_header(value.len() as u32)?;
+        }
+
+        // Now write the string encoded as UTF-8
+        self.write_bytes(value.as_bytes())
+    }
+
+    fn write_mod_settings(&mut self, mod_settings: &ModSettings) -> Result<()> {
+        self.encoding = mod_settings.encoding();
+        if let Some(version) = &mod_settings.version {
+            // Write the version first
+            self.write_version(u64::from(version.clone()))?;
+
+            // Next is a bool always set to false
+            self.write_bool(false)?;
+        }
+
+        // Then our top-level dictionary property tree
+        let sections = [
+            ("startup", &mod_settings.startup),
+            ("runtime-global", &mod_settings.runtime_global),
+            ("runtime-per-user", &mod_settings.runtime_per_user),
+        ];
+        self.write_dictionary(
+            sections.iter().map(|(k, v)| (*k, *v)),
+            mod_settings.any_type,
+        )
+    }
+
+    fn write_save_header(&mut self, header: &SaveHeader) -> Result<()> {
+        // Field order mirrors Deserialiser::parse_save_header
+        self.write_version(u64::from(header.factorio_version.clone()))?;
+        self.encoding = Encoding::for_version(&header.factorio_version);
+        let layout = self.encoding;
+
+        // Next is a single unused byte
+        if layout.header_unused_byte {
+            self.write_bool(false)?;
+        }
+
+        self.write_string_saveheader(&header.campaign)?;
+        self.write_string_saveheader(&header.name)?;
+        self.write_string_saveheader(&header.base_mod)?;
+        self.write_u8(header.difficulty.into())?;
+        self.write_bool(header.finished)?;
+        self.write_bool(header.player_won)?;
+        self.write_string_saveheader(&header.next_level)?;
+        self.write_optional_field(
+            layout.can_continue,
+            "can_continue",
+            &header.can_continue,
+            |s, v| s.write_bool(*v),
+        )?;
+        self.write_optional_field(
+            layout.can_continue,
+            "finished_but_continuing",
+            &header.finished_but_continuing,
+            |s, v| s.write_bool(*v),
+        )?;
+        self.write_bool(header.saving_replay)?;
+        self.write_optional_field(
+            layout.allow_non_admin_debug_options,
+            "allow_non_admin_debug_options",
+            &header.allow_non_admin_debug_options,
+            |s, v| s.write_bool(*v),
+        )?;
+        self.write_version48(&header.loaded_from)?;
+
+        // Build number width depends on the game version, so the two have to agree
+        match (&header.loaded_from_build, layout.header_2_0) {
+            (BuildNumber::Build32(build), true) => self.write_u32(*build)?,
+            (BuildNumber::Build16(build), false) => self.write_u16(*build)?,
+            (build, _) => {
+                return Err(Error::Syntax(format!(
+                    "Build number {:?} does not match game version {}",
+                    build, header.factorio_version
+                )))
+            }
+        }
+
+        self.write_u8(header.allowed_commands.into())?;
+
+        // The 4 unknown bytes introduced in 2.0, see Deserialiser::parse_save_header
+        match (header.unknown_2_0, layout.header_2_0) {
+            (Some(bytes), true) => {
+                for b in &bytes {
+                    self.write_u8(*b)?;
+                }
+            }
+            (None, false) => (),
+            (bytes, _) => {
+                return Err(Error::Syntax(format!(
+                    "Unknown 2.0 bytes {:?} do not match game version {}",
+                    bytes, header.factorio_version
+                )))
+            }
+        }
+
+        // Mod list, with the count first
+        self.write_u32_header(header.mods.len() as u32)?;
+        for m in &header.mods {
+            self.write_string_saveheader(&m.name)?;
+            self.write_version48(&m.version)?;
+            self.write_optional_field(layout.mod_crc, "crc", &m.crc, |s, v| s.write_u32(*v))?;
+        }
+
+        Ok(())
+    }
+
+    fn write_level_init(&mut self, level_init: &LevelInit) -> Result<()> {
+        self.write_save_header(&level_init.header)?;
+        self.write_u32(level_init.startup_settings_crc)?;
+        self.write_property_tree(&level_init.startup_settings)?;
+        self.write_bytes(&level_init.before_map_gen_settings)?;
+        if let Some(settings) = &level_init.map_gen_settings {
+            if !self.encoding.map_gen_settings {
+                return Err(Error::Syntax(format!(
+                    "Map generation settings can't be written for game version {}",
+                    level_init.header.factorio_version
+                )));
             }
+            self.write_map_gen_settings(settings)?;
+        }
+        self.write_bytes(&level_init.remainder)
+    }
+
+    fn write_map_gen_settings(&mut self, settings: &MapGenSettings) -> Result<()> {
+        // Field order mirrors Deserialiser::parse_map_gen_settings
+        let layout = self.encoding;
+        let before_2_0 = !layout.header_2_0;
+        self.write_optional_field(
+            before_2_0,
+            "terrain_segmentation",
+            &settings.terrain_segmentation,
+            |s, v| s.write_float(v.unwrap_or_default()),
+        )?;
+        self.write_optional_field(before_2_0, "water", &settings.water, |s, v| {
+            s.write_float(v.unwrap_or_default())
+        })?;
+
+        self.write_u32_header(settings.autoplace_controls.len() as u32)?;
+        for (name, control) in &settings.autoplace_controls {
+            self.write_string_saveheader(name)?;
+            self.write_float(control.frequency)?;
+            self.write_float(control.size)?;
+            self.write_float(control.richness)?;
+        }
+
+        // No autoplace settings
+        self.write_u32_header(0)?;
+
+        self.write_bool(settings.default_enable_all_autoplace_controls)?;
+        self.write_u32(settings.seed)?;
+        self.write_u32(settings.width)?;
+        self.write_u32(settings.height)?;
+        self.write_map_position(&settings.area_to_generate_at_start.left_top)?;
+        self.write_map_position(&settings.area_to_generate_at_start.right_bottom)?;
+        self.write_bytes(&settings.unknown)?;
+        self.write_float(settings.starting_area)?;
+        self.write_bool(settings.peaceful_mode)?;
+        self.write_optional_field(
+            layout.header_2_0,
+            "no_enemies_mode",
+            &settings.no_enemies_mode,
+            |s, v| s.write_bool(*v),
+        )?;
+
+        self.write_u32_header(settings.starting_points.len() as u32)?;
+        for position in &settings.starting_points {
+            self.write_map_position(position)?;
+        }
 
-            // Now write the string encoded as UTF-8
-            self.bytes.extend(value.into_bytes());
+        // No property expression names
+        self.write_u32_header(0)?;
+
+        let cliff = &settings.cliff_settings;
+        self.write_string_saveheader(&cliff.name)?;
+        self.write_optional_field(layout.header_2_0, "control", &cliff.control, |s, v| {
+            s.write_string_saveheader(v.as_deref().unwrap_or_default())
+        })?;
+        self.write_float(cliff.cliff_elevation_0)?;
+        self.write_float(cliff.cliff_elevation_interval)?;
+        self.write_float(cliff.richness)?;
+        self.write_optional_field(
+            layout.header_2_0,
+            "cliff_smoothing",
+            &cliff.cliff_smoothing,
+            |s, v| s.write_float(v.unwrap_or_default()),
+        )?;
+
+        // No territory settings
+        if layout.header_2_0 {
+            self.write_bool(false)?;
         }
+
+        Ok(())
+    }
+
+    fn write_map_position(&mut self, position: &MapPosition) -> Result<()> {
+        // Always absolute, see Deserialiser::parse_map_position
+        self.write_bytes(&i16::MAX.to_le_bytes())?;
+        self.write_bytes(&((position.x * 256.0).round() as i32).to_le_bytes())?;
+        self.write_bytes(&((position.y * 256.0).round() as i32).to_le_bytes())
+    }
+
+    fn write_property_tree(&mut self, value: &PropertyTree) -> Result<()> {
+        self.write_property_tree_node(value, false)
     }
 
-    fn write_property_tree(&mut self, value: PropertyTree) -> Result<()> {
+    fn write_property_tree_node(&mut self, value: &PropertyTree, any_type: bool) -> Result<()> {
         match value {
             PropertyTree::None => {
                 // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::None.try_into()?);
+                self.write_u8(PropertyTreeType::None.try_into()?)?;
 
                 // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
+                self.write_bool(any_type)?;
             }
             PropertyTree::Bool(bool) => {
                 // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::Bool.try_into()?);
+                self.write_u8(PropertyTreeType::Bool.try_into()?)?;
 
                 // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
+                self.write_bool(any_type)?;
 
                 // 1 bool, the actual value
-                self.write_bool(bool);
+                self.write_bool(*bool)?;
             }
             PropertyTree::Number(double) => {
                 // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::Number.try_into()?);
+                self.write_u8(PropertyTreeType::Number.try_into()?)?;
 
                 // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
+                self.write_bool(any_type)?;
 
                 // 1 double
-                self.write_double(double);
+                self.write_double(*double)?;
             }
             PropertyTree::String(string) => {
                 // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::String.try_into()?);
+                self.write_u8(PropertyTreeType::String.try_into()?)?;
 
                 // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
+                self.write_bool(any_type)?;
 
                 // 1 string
-                self.write_string(string);
+                self.write_string(string)?;
             }
-            PropertyTree::List(list) => {
+            PropertyTree::SignedInteger(_) | PropertyTree::UnsignedInteger(_)
+                if !self.encoding.integer_types =>
+            {
+                // Older versions only have doubles
+                let double = value.integer_as_double().ok_or_else(|| {
+                    Error::Syntax(format!(
+                        "{:?} cannot be stored exactly as a double before Factorio 2.0",
+                        value
+                    ))
+                })?;
+                let double = PropertyTree::Number(double);
+                self.write_property_tree_node(&double, any_type)?;
+            }
+            PropertyTree::SignedInteger(int) => {
                 // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::List.try_into()?);
+                self.write_u8(PropertyTreeType::SignedInteger.try_into()?)?;
 
                 // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
+                self.write_bool(any_type)?;
 
-                // 1 u32 representing the number of elements
-                self.write_u32(list.len() as u32);
+                // 1 signed 64-bit int
+                self.write_bytes(&int.to_le_bytes())?;
+            }
+            PropertyTree::UnsignedInteger(int) => {
+                // 1 byte representing PropertyTreeType
+                self.write_u8(PropertyTreeType::UnsignedInteger.try_into()?)?;
 
-                // Iterate over list items
-                for item in list {
-                    // 1 string, unused
-                    self.write_string(String::new());
+                // 1 bool "not important outside of Factorio internals"
+                self.write_bool(any_type)?;
 
-                    // 1 property tree
-                    self.write_property_tree(item)?;
-                }
+                // 1 unsigned 64-bit int
+                self.write_bytes(&int.to_le_bytes())?;
+            }
+            PropertyTree::List(list) => {
+                self.write_list(list.iter().map(|v| ("", v)), any_type)?;
+            }
+            PropertyTree::KeyedList(list) => {
+                self.write_list(list.iter().map(|(k, v)| (k.as_str(), v)), any_type)?;
             }
             PropertyTree::Dictionary(dict) => {
-                // 1 byte representing PropertyTreeType
-                self.write_u8(PropertyTreeType::Dictionary.try_into()?);
+                self.write_dictionary(dict.iter().map(|(k, v)| (k.as_str(), v)), any_type)?;
+            }
+            PropertyTree::AnyType(tree) => {
+                self.write_property_tree_node(tree, true)?;
+            }
+        }
 
-                // 1 bool "not important outside of Factorio internals"
-                self.write_bool(false);
-                // 1 u32 representing the number of elements
-                self.write_u32(dict.len() as u32);
+        Ok(())
+    }
 
-                // Iterate over dict items
-                for (k, v) in dict {
-                    // 1 string representing the key
-                    self.write_string(k);
+    fn write_list<'t>(
+        &mut self,
+        list: impl ExactSizeIterator<Item = (&'t str, &'t PropertyTree)>,
+        any_type: bool,
+    ) -> Result<()> {
+        // 1 byte representing PropertyTreeType
+        self.write_u8(PropertyTreeType::List.try_into()?)?;
 
-                    // 1 property tree
-                    self.write_property_tree(v)?;
-                }
-            }
+        // 1 bool "not important outside of Factorio internals"
+        self.write_bool(any_type)?;
+
+        // 1 u32 representing the number of elements
+        self.write_u32(list.len() as u32)?;
+
+        // Iterate over list items
+        for (k, v) in list {
+            // 1 string, usually empty
+            self.write_string(k)?;
+
+            // 1 property tree
+            self.write_property_tree(v)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_dictionary<'t>(
+        &mut self,
+        dict: impl ExactSizeIterator<Item = (&'t str, &'t PropertyTree)>,
+        any_type: bool,
+    ) -> Result<()> {
+        // 1 byte representing PropertyTreeType
+        self.write_u8(PropertyTreeType::Dictionary.try_into()?)?;
+
+        // 1 bool "not important outside of Factorio internals"
+        self.write_bool(any_type)?;
+        // 1 u32 representing the number of elements
+        self.write_u32(dict.len() as u32)?;
+
+        // Iterate over dict items
+        for (k, v) in dict {
+            // 1 string representing the key
+            self.write_string(k)?;
+
+            // 1 property tree
+            self.write_property_tree(v)?;
         }
 
         Ok(())
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub enum PropertyTree {
     None,
     Bool(bool),
     Number(f64),
+    /// Empty strings can be stored either with the "empty" flag set or with
+    /// a zero length. Only the second form is written, as that's what the game does.
     String(String),
     List(Vec<PropertyTree>),
     Dictionary(Vec<(String, PropertyTree)>),
+    /// Only in files from Factorio 2.0 onwards
+    SignedInteger(i64),
+    /// Only in files from Factorio 2.0 onwards
+    UnsignedInteger(u64),
+    /// A list where some items have a key. The game normally leaves these
+    /// empty, in which case the list is read as a plain `List`.
+    KeyedList(Vec<(String, PropertyTree)>),
+    /// A node with the "any-type" flag set. The flag means nothing outside
+    /// of Factorio internals, but is kept so files round-trip byte for byte.
+    AnyType(Box<PropertyTree>),
+}
+
+/// Returned by indexing when the key isn't present
+static NONE: PropertyTree = PropertyTree::None;
+
+impl PropertyTree {
+    /// The node itself, looking through any `AnyType` wrapper
+    pub fn inner(&self) -> &PropertyTree {
+        match self {
+            PropertyTree::AnyType(tree) => tree.inner(),
+            tree => tree,
+        }
+    }
+
+    pub fn inner_mut(&mut self) -> &mut PropertyTree {
+        match self {
+            PropertyTree::AnyType(tree) => tree.inner_mut(),
+            tree => tree,
+        }
+    }
+
+    pub fn into_inner(self) -> PropertyTree {
+        match self {
+            PropertyTree::AnyType(tree) => tree.into_inner(),
+            tree => tree,
+        }
+    }
+
+    pub fn is_any_type(&self) -> bool {
+        matches!(self, PropertyTree::AnyType(_))
+    }
+
+    /// Value under `key`, if this is a dictionary containing it
+    pub fn get(&self, key: &str) -> Option<&PropertyTree> {
+        self.as_dict()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut PropertyTree> {
+        self.as_dict_mut()?
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Sets `key` to `value`, keeping its position if it was already present
+    /// and appending it otherwise. Returns the previous value, if any.
+    pub fn insert<K: Into<String>>(
+        &mut self,
+        key: K,
+        value: PropertyTree,
+    ) -> Result<Option<PropertyTree>> {
+        let dict = self.as_dict_mut().ok_or_else(|| {
+            Error::Syntax("PropertyTree not dictionary type, cannot insert".to_owned())
+        })?;
+
+        let key = key.into();
+        match dict.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => Ok(Some(std::mem::replace(v, value))),
+            None => {
+                dict.push((key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes `key`, preserving the order of the remaining entries
+    pub fn remove(&mut self, key: &str) -> Option<PropertyTree> {
+        let dict = self.as_dict_mut()?;
+        let i = dict.iter().position(|(k, _)| k == key)?;
+        Some(dict.remove(i).1)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.inner() {
+            PropertyTree::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.inner() {
+            PropertyTree::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Value of either integer type, if it fits
+    pub fn as_i64(&self) -> Option<i64> {
+        match self.inner() {
+            PropertyTree::SignedInteger(i) => Some(*i),
+            PropertyTree::UnsignedInteger(u) => i64::try_from(*u).ok(),
+            _ => None,
+        }
+    }
+
+    /// Value of either integer type, if it fits
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.inner() {
+            PropertyTree::SignedInteger(i) => u64::try_from(*i).ok(),
+            PropertyTree::UnsignedInteger(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self.inner() {
+            PropertyTree::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Items of a plain list. Lists where any item has a key are read as
+    /// `KeyedList` instead and give `None` here, see [`PropertyTree::list_items`].
+    pub fn as_list(&self) -> Option<&Vec<PropertyTree>> {
+        match self.inner() {
+            PropertyTree::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_list_mut(&mut self) -> Option<&mut Vec<PropertyTree>> {
+        match self.inner_mut() {
+            PropertyTree::List(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Items of a list whether or not any of them have keys, leaving the keys out
+    pub fn list_items(&self) -> Option<impl Iterator<Item = &PropertyTree> + '_> {
+        let (plain, keyed): (&[PropertyTree], &[(String, PropertyTree)]) = match self.inner() {
+            PropertyTree::List(list) => (list, &[]),
+            PropertyTree::KeyedList(list) => (&[], list),
+            _ => return None,
+        };
+        Some(plain.iter().chain(keyed.iter().map(|(_, v)| v)))
+    }
+
+    /// Items of a list along with their keys, if any of them have one
+    pub fn as_keyed_list(&self) -> Option<&Vec<(String, PropertyTree)>> {
+        match self.inner() {
+            PropertyTree::KeyedList(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_keyed_list_mut(&mut self) -> Option<&mut Vec<(String, PropertyTree)>> {
+        match self.inner_mut() {
+            PropertyTree::KeyedList(list) => Some(list),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&Vec<(String, PropertyTree)>> {
+        match self.inner() {
+            PropertyTree::Dictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict_mut(&mut self) -> Option<&mut Vec<(String, PropertyTree)>> {
+        match self.inner_mut() {
+            PropertyTree::Dictionary(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    /// Looks up a value by a `/`-separated path of dictionary keys and list
+    /// indices, e.g. `/runtime-global/foo/value`. Like JSON pointers, `~1`
+    /// and `~0` in a key stand for `/` and `~`, and the empty path is the
+    /// tree itself.
+    pub fn pointer(&self, pointer: &str) -> Option<&PropertyTree> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape_pointer_token)
+            .try_fold(self, |tree, token| match tree.inner() {
+                PropertyTree::List(list) => list.get(token.parse::<usize>().ok()?),
+                PropertyTree::KeyedList(list) => {
+                    list.get(token.parse::<usize>().ok()?).map(|(_, v)| v)
+                }
+                node => node.get(&token),
+            })
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut PropertyTree> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer
+            .strip_prefix('/')?
+            .split('/')
+            .map(unescape_pointer_token)
+            .try_fold(self, |tree, token| match tree.inner_mut() {
+                PropertyTree::List(list) => list.get_mut(token.parse::<usize>().ok()?),
+                PropertyTree::KeyedList(list) => {
+                    list.get_mut(token.parse::<usize>().ok()?).map(|(_, v)| v)
+                }
+                node => node.get_mut(&token),
+            })
+    }
+}
+
+impl PropertyTree {
+    /// Value of either integer type as a double, if it can be stored exactly
+    pub(crate) fn integer_as_double(&self) -> Option<f64> {
+        let exact = |magnitude: u64| magnitude <= MAX_EXACT_DOUBLE_INTEGER;
+        match self {
+            PropertyTree::SignedInteger(i) if exact(i.unsigned_abs()) => Some(*i as f64),
+            PropertyTree::UnsignedInteger(u) if exact(*u) => Some(*u as f64),
+            _ => None,
+        }
+    }
+
+    /// Re-encodes, in place, any values `encoding` has no type for.
+    /// `pointer` locates this node in errors.
+    fn convert_to(&mut self, encoding: Encoding, pointer: &str) -> Result<()> {
+        let child =
+            |key: &str| format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1"));
+        match self {
+            PropertyTree::SignedInteger(_) | PropertyTree::UnsignedInteger(_)
+                if !encoding.integer_types =>
+            {
+                let double = self.integer_as_double().ok_or_else(|| {
+                    Error::Syntax(format!(
+                        "{:?} at {} cannot be stored exactly as a double before Factorio 2.0",
+                        self, pointer
+                    ))
+                })?;
+                *self = PropertyTree::Number(double);
+            }
+            PropertyTree::List(list) => {
+                for (i, item) in list.iter_mut().enumerate() {
+                    item.convert_to(encoding, &child(&i.to_string()))?;
+                }
+            }
+            PropertyTree::KeyedList(list) => {
+                for (i, (_, item)) in list.iter_mut().enumerate() {
+                    item.convert_to(encoding, &child(&i.to_string()))?;
+                }
+            }
+            PropertyTree::Dictionary(dict) => {
+                for (k, v) in dict.iter_mut() {
+                    v.convert_to(encoding, &child(k))?;
+                }
+            }
+            PropertyTree::AnyType(tree) => tree.convert_to(encoding, pointer)?,
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn unescape_pointer_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+impl std::ops::Index<&str> for PropertyTree {
+    type Output = PropertyTree;
+
+    /// Value under `key`, or `PropertyTree::None` if it isn't present
+    fn index(&self, key: &str) -> &PropertyTree {
+        self.get(key).unwrap_or(&NONE)
+    }
 }
 
 enum PropertyTreeType {
@@ -594,6 +2163,8 @@ enum PropertyTreeType {
     String,
     List,
     Dictionary,
+    SignedInteger,
+    UnsignedInteger,
 }
 
 impl TryFrom<u8> for PropertyTreeType {
@@ -607,6 +2178,8 @@ impl TryFrom<u8> for PropertyTreeType {
             3 => Ok(PropertyTreeType::String),
             4 => Ok(PropertyTreeType::List),
             5 => Ok(PropertyTreeType::Dictionary),
+            6 => Ok(PropertyTreeType::SignedInteger),
+            7 => Ok(PropertyTreeType::UnsignedInteger),
             _ => Err(Error::OutOfRange),
         }
     }
@@ -623,11 +2196,20 @@ impl TryFrom<PropertyTreeType> for u8 {
             PropertyTreeType::String => Ok(3),
             PropertyTreeType::List => Ok(4),
             PropertyTreeType::Dictionary => Ok(5),
+            PropertyTreeType::SignedInteger => Ok(6),
+            PropertyTreeType::UnsignedInteger => Ok(7),
         }
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+/// Full game version, e.g. `2.0.28.0`. The last part is the developer
+/// version, which is 0 in released builds.
+///
+/// Orders component by component. Serialises as a struct by default, or use
+/// [`crate::dotted`] for the dotted string form.
+#[derive(
+    Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize,
+)]
 pub struct Version {
     main: u16,
     major: u16,
@@ -635,35 +2217,231 @@ pub struct Version {
     developer: u16,
 }
 
+impl Version {
+    pub fn new(main: u16, major: u16, minor: u16, developer: u16) -> Self {
+        Version {
+            main,
+            major,
+            minor,
+            developer,
+        }
+    }
+
+    pub fn main(&self) -> u16 {
+        self.main
+    }
+
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+
+    pub fn developer(&self) -> u16 {
+        self.developer
+    }
+}
+
 impl Display for Version {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{}.{}.{}", self.main, self.major, self.minor, self.developer)
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.main, self.major, self.minor, self.developer
+        )
+    }
+}
+
+/// Accepts `main.major.minor.developer`, or `main.major.minor` with a developer version of 0
+impl FromStr for Version {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match parse_dotted(s)?[..] {
+            [main, major, minor] => Ok(Version::new(main, major, minor, 0)),
+            [main, major, minor, developer] => Ok(Version::new(main, major, minor, developer)),
+            _ => Err(Error::Syntax(format!("Invalid version '{}'", s))),
+        }
     }
 }
 
 impl From<Version> for u64 {
     fn from(value: Version) -> Self {
-        let ret = value.developer as u64
+        value.developer as u64
             | (value.minor as u64) << 16
             | (value.major as u64) << 32
-            | (value.main as u64) << 48;
-        ret
+            | (value.main as u64) << 48
     }
 }
 
-#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+impl From<u64> for Version {
+    fn from(value: u64) -> Self {
+        Version::new(
+            (value >> 48) as u16,
+            (value >> 32) as u16,
+            (value >> 16) as u16,
+            value as u16,
+        )
+    }
+}
+
+/// Developer version is 0
+impl From<Version48> for Version {
+    fn from(value: Version48) -> Self {
+        Version::new(value.main, value.major, value.minor, 0)
+    }
+}
+
+/// Drops the developer version
+impl From<Version> for Version48 {
+    fn from(value: Version) -> Self {
+        Version48::new(value.main, value.major, value.minor)
+    }
+}
+
+/// Game version without the developer version, e.g. `1.1.110`, as used for
+/// mod versions and in save headers.
+///
+/// Orders component by component. Serialises as a struct by default, or use
+/// [`crate::dotted`] for the dotted string form.
+#[derive(
+    Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize, serde::Serialize,
+)]
 pub struct Version48 {
     main: u16,
     major: u16,
     minor: u16,
 }
 
+impl Version48 {
+    pub fn new(main: u16, major: u16, minor: u16) -> Self {
+        Version48 { main, major, minor }
+    }
+
+    pub fn main(&self) -> u16 {
+        self.main
+    }
+
+    pub fn major(&self) -> u16 {
+        self.major
+    }
+
+    pub fn minor(&self) -> u16 {
+        self.minor
+    }
+}
+
+impl FromStr for Version48 {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match parse_dotted(s)?[..] {
+            [main, major, minor] => Ok(Version48::new(main, major, minor)),
+            _ => Err(Error::Syntax(format!("Invalid version '{}'", s))),
+        }
+    }
+}
+
+/// Splits a dotted version string into its numeric components
+fn parse_dotted(s: &str) -> Result<Vec<u16>> {
+    s.split('.')
+        .map(|part| {
+            part.parse::<u16>()
+                .map_err(|_| Error::Syntax(format!("Invalid version '{}'", s)))
+        })
+        .collect()
+}
+
 impl Display for Version48 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}.{}.{}", self.main, self.major, self.minor)
     }
 }
 
+/// Whether console commands are allowed, as stored in the save header
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum AllowedCommands {
+    No,
+    Yes,
+    /// Any other value, such as the admins-only setting, kept so it can be written back
+    Unknown(u8),
+}
+
+impl From<u8> for AllowedCommands {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => AllowedCommands::No,
+            1 => AllowedCommands::Yes,
+            x => AllowedCommands::Unknown(x),
+        }
+    }
+}
+
+impl From<AllowedCommands> for u8 {
+    fn from(value: AllowedCommands) -> Self {
+        match value {
+            AllowedCommands::No => 0,
+            AllowedCommands::Yes => 1,
+            AllowedCommands::Unknown(x) => x,
+        }
+    }
+}
+
+impl Display for AllowedCommands {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AllowedCommands::No => write!(f, "No"),
+            AllowedCommands::Yes => write!(f, "Yes"),
+            AllowedCommands::Unknown(x) => write!(f, "Unknown ({})", x),
+        }
+    }
+}
+
+/// Game difficulty as stored in the save header, matching `defines.difficulty`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    /// Any value the game isn't known to write, kept so it can be written back
+    Unknown(u8),
+}
+
+impl From<u8> for Difficulty {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Normal,
+            2 => Difficulty::Hard,
+            x => Difficulty::Unknown(x),
+        }
+    }
+}
+
+impl From<Difficulty> for u8 {
+    fn from(value: Difficulty) -> Self {
+        match value {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+            Difficulty::Unknown(x) => x,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+            Difficulty::Unknown(x) => write!(f, "Unknown ({})", x),
+        }
+    }
+}
+
 #[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub enum BuildNumber {
     Build16(u16),
@@ -687,14 +2465,130 @@ mod tests {
 
     #[test]
     fn can_convert_between_byte_and_propertytreetype() {
-        let bytes: Vec<u8> = (0..6).collect();
+        let bytes: Vec<u8> = (0..8).collect();
         for b in bytes {
             let r = b.try_into();
             assert!(r.is_ok());
             let t: PropertyTreeType = r.unwrap();
-            let r2 = t.try_into();
+            let r2: Result<u8> = t.try_into();
             assert!(r2.is_ok());
             assert_eq!(b, r2.unwrap());
         }
     }
+
+    #[test]
+    fn can_convert_between_byte_and_difficulty() {
+        for b in 0..=u8::MAX {
+            assert_eq!(b, u8::from(Difficulty::from(b)));
+        }
+        assert_eq!(Difficulty::Normal, Difficulty::from(1));
+        assert_eq!("Normal", Difficulty::Normal.to_string());
+        assert_eq!("Unknown (7)", Difficulty::Unknown(7).to_string());
+    }
+
+    #[test]
+    fn can_look_up_propertytree_by_key_and_pointer() {
+        let mut tree = PropertyTree::Dictionary(vec![(
+            "runtime-global".to_owned(),
+            PropertyTree::Dictionary(vec![
+                (
+                    "a/b".to_owned(),
+                    PropertyTree::List(vec![PropertyTree::Bool(true)]),
+                ),
+                ("c".to_owned(), PropertyTree::Number(1.5)),
+            ]),
+        )]);
+
+        assert_eq!(Some(1.5), tree["runtime-global"]["c"].as_f64());
+        assert_eq!(PropertyTree::None, tree["missing"]["c"]);
+        assert_eq!(
+            Some(true),
+            tree.pointer("/runtime-global/a~1b/0")
+                .and_then(|t| t.as_bool())
+        );
+        assert_eq!(None, tree.pointer("/runtime-global/a~1b/1"));
+        assert_eq!(None, tree.pointer("runtime-global"));
+        assert_eq!(Some(&tree.clone()), tree.pointer(""));
+
+        // insert keeps position of existing keys
+        let global = tree.pointer_mut("/runtime-global").unwrap();
+        global.insert("d", PropertyTree::None).unwrap();
+        let old = global.insert("a/b", PropertyTree::Bool(false)).unwrap();
+        assert!(old.is_some());
+        let keys: Vec<&str> = global
+            .as_dict()
+            .unwrap()
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect();
+        assert_eq!(vec!["a/b", "c", "d"], keys);
+
+        assert_eq!(Some(PropertyTree::Number(1.5)), global.remove("c"));
+        assert_eq!(None, global.remove("c"));
+        assert!(PropertyTree::Bool(true)
+            .insert("x", PropertyTree::None)
+            .is_err());
+    }
+
+    #[test]
+    fn can_parse_and_compare_versions() -> Result<()> {
+        let v: Version = "1.1.110".parse()?;
+        assert_eq!(Version::new(1, 1, 110, 0), v);
+        assert_eq!(v, "1.1.110.0".parse()?);
+        assert_eq!("1.1.110.0", v.to_string());
+        assert!(v < "2.0.0".parse()?);
+        assert!(Version::new(1, 1, 9, 0) < Version::new(1, 1, 10, 0));
+        assert_eq!(v, Version::from(u64::from(v.clone())));
+
+        let v48: Version48 = "2.0.28".parse()?;
+        assert_eq!((2, 0, 28), (v48.main(), v48.major(), v48.minor()));
+        assert_eq!(Version::new(2, 0, 28, 0), Version::from(v48.clone()));
+        assert_eq!(v48, Version48::from(Version::new(2, 0, 28, 5)));
+
+        for bad in &["", "1", "1.1", "1.1.1.1.1", "1.1.x", "1.1.70000", "1..1"] {
+            assert!(bad.parse::<Version>().is_err(), "{}", bad);
+        }
+        assert!("1.1.1.1".parse::<Version48>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn keeps_list_item_keys() -> Result<()> {
+        // list of two strings, the second keyed
+        let bytes = [
+            4, 0, 2, 0, 0, 0, 0, 0, 3, 0, 0, 1, b'a', 0, 1, b'k', 3, 0, 0, 1, b'b',
+        ];
+        let mut d = Deserialiser::new(&bytes[..], ParseLimits::default());
+        let tree = d.parse_property_tree()?;
+        assert_eq!(
+            Some(&vec![
+                ("".to_owned(), PropertyTree::String("a".to_owned())),
+                ("k".to_owned(), PropertyTree::String("b".to_owned())),
+            ]),
+            tree.as_keyed_list()
+        );
+        assert_eq!(Some("b"), tree.pointer("/1").and_then(|t| t.as_str()));
+        assert_eq!(None, tree.as_list());
+        let items: Vec<_> = tree
+            .list_items()
+            .unwrap()
+            .filter_map(|t| t.as_str())
+            .collect();
+        assert_eq!(vec!["a", "b"], items);
+        assert_eq!(
+            1,
+            PropertyTree::List(vec![PropertyTree::None])
+                .list_items()
+                .unwrap()
+                .count()
+        );
+        assert!(PropertyTree::None.list_items().is_none());
+
+        let mut out = vec![];
+        Serialiser::new(&mut out).write_property_tree(&tree)?;
+        assert_eq!(&bytes[..], &out[..]);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,295 @@
+use crate::error::{Error, Result};
+use crate::schema::{ModSettings, PropertyTree};
+use std::convert::TryFrom;
+
+/// One of the three sections of `mod-settings.dat`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum SettingsSection {
+    Startup,
+    RuntimeGlobal,
+    RuntimePerUser,
+}
+
+impl SettingsSection {
+    /// Key of the section in the top-level dictionary, e.g. `runtime-global`
+    pub fn key(&self) -> &'static str {
+        match self {
+            SettingsSection::Startup => "startup",
+            SettingsSection::RuntimeGlobal => "runtime-global",
+            SettingsSection::RuntimePerUser => "runtime-per-user",
+        }
+    }
+}
+
+impl TryFrom<&str> for SettingsSection {
+    type Error = Error;
+
+    fn try_from(key: &str) -> Result<Self> {
+        match key {
+            "startup" => Ok(SettingsSection::Startup),
+            "runtime-global" => Ok(SettingsSection::RuntimeGlobal),
+            "runtime-per-user" => Ok(SettingsSection::RuntimePerUser),
+            _ => Err(Error::Syntax(format!("Unknown settings section '{}'", key))),
+        }
+    }
+}
+
+/// Typed value of a single mod setting
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub enum SettingValue {
+    Bool(bool),
+    /// Stored as a double with no fractional part, or as an integer from Factorio 2.0
+    Int(i64),
+    Double(f64),
+    String(String),
+    Color(Color),
+    /// Anything that doesn't fit the above
+    Other(PropertyTree),
+}
+
+impl From<&PropertyTree> for SettingValue {
+    fn from(tree: &PropertyTree) -> Self {
+        match tree.inner() {
+            PropertyTree::Bool(b) => SettingValue::Bool(*b),
+            // int-setting and double-setting are both stored as doubles, so go by the value
+            PropertyTree::Number(n) => {
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 {
+                    SettingValue::Int(*n as i64)
+                } else {
+                    SettingValue::Double(*n)
+                }
+            }
+            PropertyTree::SignedInteger(i) => SettingValue::Int(*i),
+            PropertyTree::UnsignedInteger(u) => match i64::try_from(*u) {
+                Ok(i) => SettingValue::Int(i),
+                Err(_) => SettingValue::Other(tree.inner().clone()),
+            },
+            PropertyTree::String(s) => SettingValue::String(s.clone()),
+            other => match Color::try_from(other) {
+                Ok(color) => SettingValue::Color(color),
+                Err(_) => SettingValue::Other(other.clone()),
+            },
+        }
+    }
+}
+
+impl TryFrom<SettingValue> for PropertyTree {
+    type Error = Error;
+
+    /// Fails for ints that don't fit exactly in the double they are stored as
+    fn try_from(value: SettingValue) -> Result<Self> {
+        Ok(match value {
+            SettingValue::Bool(b) => PropertyTree::Bool(b),
+            SettingValue::Int(i) => int_as_number(i)?,
+            SettingValue::Double(d) => PropertyTree::Number(d),
+            SettingValue::String(s) => PropertyTree::String(s),
+            SettingValue::Color(color) => PropertyTree::from(color),
+            SettingValue::Other(tree) => tree,
+        })
+    }
+}
+
+/// `i` as a double, as long as no precision is lost
+fn int_as_number(i: i64) -> Result<PropertyTree> {
+    PropertyTree::SignedInteger(i)
+        .integer_as_double()
+        .map(PropertyTree::Number)
+        .ok_or_else(|| {
+            Error::Syntax(format!(
+                "Int setting value {} cannot be stored exactly as a double",
+                i
+            ))
+        })
+}
+
+/// Value of a `color-setting`, with each component in the range 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl TryFrom<&PropertyTree> for Color {
+    type Error = Error;
+
+    fn try_from(tree: &PropertyTree) -> Result<Self> {
+        match tree.as_dict() {
+            Some(dict) if dict.len() == 4 => {}
+            _ => return Err(Error::Syntax("PropertyTree not color type".to_owned())),
+        }
+
+        let component = |name: &str| {
+            tree.get(name)
+                .and_then(PropertyTree::as_f64)
+                .ok_or_else(|| {
+                    Error::Syntax(format!(
+                        "Color component '{}' missing or not number type",
+                        name
+                    ))
+                })
+        };
+        Ok(Color {
+            r: component("r")?,
+            g: component("g")?,
+            b: component("b")?,
+            a: component("a")?,
+        })
+    }
+}
+
+impl From<Color> for PropertyTree {
+    fn from(color: Color) -> Self {
+        // Same order the game writes them in
+        PropertyTree::Dictionary(vec![
+            ("r".to_owned(), PropertyTree::Number(color.r)),
+            ("g".to_owned(), PropertyTree::Number(color.g)),
+            ("b".to_owned(), PropertyTree::Number(color.b)),
+            ("a".to_owned(), PropertyTree::Number(color.a)),
+        ])
+    }
+}
+
+impl ModSettings {
+    pub fn section(&self, section: SettingsSection) -> &PropertyTree {
+        match section {
+            SettingsSection::Startup => &self.startup,
+            SettingsSection::RuntimeGlobal => &self.runtime_global,
+            SettingsSection::RuntimePerUser => &self.runtime_per_user,
+        }
+    }
+
+    pub fn section_mut(&mut self, section: SettingsSection) -> &mut PropertyTree {
+        match section {
+            SettingsSection::Startup => &mut self.startup,
+            SettingsSection::RuntimeGlobal => &mut self.runtime_global,
+            SettingsSection::RuntimePerUser => &mut self.runtime_per_user,
+        }
+    }
+
+    /// Value of the setting `name`, if it is present in `section`
+    pub fn get(&self, section: SettingsSection, name: &str) -> Option<SettingValue> {
+        // Each setting is a dictionary with the actual value under `value`
+        self.section(section)
+            .get(name)?
+            .get("value")
+            .map(SettingValue::from)
+    }
+
+    /// Sets the setting `name` in `section`, adding it if it isn't there already
+    pub fn set(&mut self, section: SettingsSection, name: &str, value: SettingValue) -> Result<()> {
+        // Factorio 2.0 stores int settings as integers rather than doubles. Integral
+        // doubles read back as ints too, so an existing setting keeps its stored type.
+        let existing = self
+            .section(section)
+            .get(name)
+            .and_then(|setting| setting.get("value"))
+            .map(PropertyTree::inner);
+        let value = match (value, existing) {
+            (SettingValue::Int(i), Some(PropertyTree::Number(_))) => int_as_number(i)?,
+            (SettingValue::Int(i), Some(PropertyTree::UnsignedInteger(_))) if i >= 0 => {
+                PropertyTree::UnsignedInteger(i as u64)
+            }
+            (SettingValue::Int(i), _) if self.encoding().integer_types => {
+                PropertyTree::SignedInteger(i)
+            }
+            (value, _) => PropertyTree::try_from(value)?,
+        };
+
+        let tree = self.section_mut(section);
+        if tree.as_dict().is_none() {
+            return Err(Error::Syntax(format!(
+                "Settings section '{}' not dictionary type",
+                section.key()
+            )));
+        }
+
+        match tree.get_mut(name) {
+            // Replace just the value, leaving anything else alongside it untouched,
+            // including the flag on settings wrapped in AnyType
+            Some(setting) if setting.as_dict().is_some() => {
+                setting.insert("value", value)?;
+            }
+            Some(other) => *other = setting_tree(value),
+            None => {
+                tree.insert(name, setting_tree(value))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the setting `name` from `section`, returning its value if it was present
+    pub fn remove(&mut self, section: SettingsSection, name: &str) -> Option<SettingValue> {
+        self.section_mut(section)
+            .remove(name)?
+            .get("value")
+            .map(SettingValue::from)
+    }
+}
+
+/// Wraps a value in the `{ "value": ... }` dictionary each setting is stored as
+fn setting_tree(value: PropertyTree) -> PropertyTree {
+    PropertyTree::Dictionary(vec![("value".to_owned(), value)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_convert_between_settingvalue_and_propertytree() {
+        let values = vec![
+            SettingValue::Bool(true),
+            SettingValue::Int(-3),
+            SettingValue::Double(0.25),
+            SettingValue::String("hello".to_owned()),
+            SettingValue::Color(Color {
+                r: 1.0,
+                g: 0.5,
+                b: 0.0,
+                a: 1.0,
+            }),
+            SettingValue::Other(PropertyTree::None),
+        ];
+        for value in values {
+            let tree = PropertyTree::try_from(value.clone()).unwrap();
+            assert_eq!(value, SettingValue::from(&tree));
+        }
+    }
+
+    #[test]
+    fn ints_must_be_exact_as_doubles() {
+        assert_eq!(
+            PropertyTree::Number(-9007199254740992.0),
+            PropertyTree::try_from(SettingValue::Int(-(1 << 53))).unwrap()
+        );
+        assert!(PropertyTree::try_from(SettingValue::Int((1 << 53) + 1)).is_err());
+        assert!(PropertyTree::try_from(SettingValue::Int(i64::MAX)).is_err());
+    }
+
+    #[test]
+    fn unsigned_integers_are_ints_where_they_fit() {
+        assert_eq!(
+            SettingValue::Int(5),
+            SettingValue::from(&PropertyTree::UnsignedInteger(5))
+        );
+        assert_eq!(
+            SettingValue::Other(PropertyTree::UnsignedInteger(u64::MAX)),
+            SettingValue::from(&PropertyTree::UnsignedInteger(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn dictionary_without_all_components_is_not_color() {
+        let tree = PropertyTree::Dictionary(vec![
+            ("r".to_owned(), PropertyTree::Number(1.0)),
+            ("g".to_owned(), PropertyTree::Number(1.0)),
+            ("b".to_owned(), PropertyTree::Number(1.0)),
+            ("x".to_owned(), PropertyTree::Number(1.0)),
+        ]);
+        assert!(Color::try_from(&tree).is_err());
+        assert_eq!(SettingValue::Other(tree.clone()), SettingValue::from(&tree));
+    }
+}
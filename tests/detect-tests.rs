@@ -0,0 +1,95 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use factorio_file_parser::{detect_format, parse_any, AnyFactorioFile, Error, FileKind};
+
+use common::build_save_zip;
+
+#[test]
+fn can_detect_fixtures() -> Result<(), Box<dyn std::error::Error>> {
+    let mod_settings = fs::read(Path::new("tests").join("mod-settings.dat"))?;
+    assert_eq!(Some(FileKind::ModSettings), detect_format(&mod_settings));
+
+    // pre-0.17 layout without the version
+    assert_eq!(
+        Some(FileKind::ModSettings),
+        detect_format(&mod_settings[9..])
+    );
+
+    for name in &[
+        "vanilla.level-init.dat",
+        "spaceage.level-init.dat",
+        "spaceage-withmods.level-init.dat",
+    ] {
+        let bytes = fs::read(Path::new("tests").join(name))?;
+        assert_eq!(
+            Some(FileKind::SaveHeader),
+            detect_format(&bytes),
+            "{}",
+            name
+        );
+
+        let zip = build_save_zip(&[("my-save/level-init.dat", &bytes)])?;
+        assert_eq!(Some(FileKind::SaveZip), detect_format(&zip), "{}", name);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn rejects_unknown_formats() {
+    for bytes in &[
+        &b""[..],
+        &b"\x89PNG\r\n\x1a\n"[..],
+        &br#"{"mods": []}"#[..],
+        &[0xff; 64][..],
+        &[0; 8][..],
+    ] {
+        assert_eq!(None, detect_format(bytes), "{:?}", bytes);
+        assert!(matches!(parse_any(bytes), Err(Error::Syntax(_))));
+    }
+}
+
+#[test]
+fn rejects_zips_that_are_not_saves() -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = fs::read(Path::new("tests").join("spaceage.level-init.dat"))?;
+    for entries in &[
+        // a mod
+        vec![
+            ("my-mod_1.0.0/info.json", &b"{}"[..]),
+            ("my-mod_1.0.0/data.lua", &b""[..]),
+        ],
+        // level-init.dat too deep to be a save's
+        vec![("saves/my-save/level-init.dat", &bytes[..])],
+    ] {
+        let zip = build_save_zip(entries)?;
+        assert_eq!(None, detect_format(&zip));
+        assert!(matches!(parse_any(&zip), Err(Error::Syntax(_))));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_parse_any() -> Result<(), Box<dyn std::error::Error>> {
+    let mod_settings = fs::read(Path::new("tests").join("mod-settings.dat"))?;
+    match parse_any(&mod_settings)? {
+        AnyFactorioFile::ModSettings(ms) => assert!(ms.version.is_some()),
+        other => panic!("expected ModSettings, got {:?}", other.kind()),
+    }
+
+    let bytes = fs::read(Path::new("tests").join("spaceage-withmods.level-init.dat"))?;
+    let level_init = parse_any(&bytes)?;
+    assert_eq!(FileKind::SaveHeader, level_init.kind());
+
+    let zip = build_save_zip(&[("my-save/level-init.dat", &bytes)])?;
+    let save_zip = parse_any(&zip)?;
+    assert_eq!(FileKind::SaveZip, save_zip.kind());
+
+    let header = level_init.save_header().unwrap();
+    assert_eq!(11, header.mods.len());
+    assert_eq!(header.name, save_zip.save_header().unwrap().name);
+
+    Ok(())
+}
@@ -1,6 +1,19 @@
-use std::{convert::TryFrom, fs, path::Path};
+mod common;
 
-use factorio_file_parser::SaveHeader;
+use std::{
+    convert::{TryFrom, TryInto},
+    fs,
+    io::Cursor,
+    path::Path,
+};
+
+use factorio_file_parser::{
+    AllowedCommands, AutoplaceControl, BuildNumber, Dependency, Difficulty, Error, LevelInit,
+    MapPosition, ParseLimits, ParseOptions, PropertyTree, SaveHeader, SaveHeaderMod, Version,
+    Version48,
+};
+
+use common::build_save_zip;
 
 #[test]
 fn can_deserialise_pre_2_0_vanilla() -> Result<(), Box<dyn std::error::Error>> {
@@ -45,3 +58,709 @@ fn can_deserialise_pre_2_0_withmods() -> Result<(), Box<dyn std::error::Error>>
 
     Ok(())
 }
+
+fn assert_serialise_matches_fixture(fixture: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join(fixture);
+    let bytes = fs::read(path)?;
+
+    // deserialise, then serialise back
+    let header = SaveHeader::try_from(bytes.as_ref())?;
+    let bytes2: Vec<u8> = header.try_into()?;
+
+    // level-init.dat continues past the header, so only compare the header portion
+    assert_eq!(&bytes[..bytes2.len()], bytes2.as_slice());
+
+    Ok(())
+}
+
+#[test]
+fn can_serialise_pre_2_0_vanilla() -> Result<(), Box<dyn std::error::Error>> {
+    assert_serialise_matches_fixture("vanilla.level-init.dat")
+}
+
+#[test]
+fn can_serialise_spaceage_vanilla() -> Result<(), Box<dyn std::error::Error>> {
+    assert_serialise_matches_fixture("spaceage.level-init.dat")
+}
+
+#[test]
+fn can_serialise_spaceage_withmods() -> Result<(), Box<dyn std::error::Error>> {
+    assert_serialise_matches_fixture("spaceage-withmods.level-init.dat")
+}
+
+#[test]
+fn preserves_unknown_2_0_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    let mut header = SaveHeader::try_from(bytes.as_ref())?;
+    assert_eq!(Some(SaveHeader::EXPECTED_UNKNOWN_2_0), header.unknown_2_0);
+    assert!(!header.has_unexpected_unknown_2_0());
+
+    // unexpected values should be reported and survive a round trip
+    header.unknown_2_0 = Some([0x01, 0x02, 0x03, 0x04]);
+    assert!(header.has_unexpected_unknown_2_0());
+    let bytes2: Vec<u8> = header.try_into()?;
+    let header2 = SaveHeader::try_from(bytes2.as_ref())?;
+    assert_eq!(Some([0x01, 0x02, 0x03, 0x04]), header2.unknown_2_0);
+
+    Ok(())
+}
+
+#[test]
+fn pre_2_0_has_no_unknown_2_0_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("vanilla.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    let header = SaveHeader::try_from(bytes.as_ref())?;
+    assert_eq!(None, header.unknown_2_0);
+    assert!(!header.has_unexpected_unknown_2_0());
+
+    Ok(())
+}
+
+#[test]
+fn can_patch_and_reserialise() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    // flip a flag and drop a mod, then read it back
+    let mut header = SaveHeader::try_from(bytes.as_ref())?;
+    header.allowed_commands = AllowedCommands::No;
+    let removed = header.mods.pop().unwrap();
+    let bytes2: Vec<u8> = header.clone().try_into()?;
+    let header2 = SaveHeader::try_from(bytes2.as_ref())?;
+
+    assert_eq!(header.allowed_commands, header2.allowed_commands);
+    assert_eq!(header.mods.len(), header2.mods.len());
+    assert!(header2.mods.iter().all(|m| m.name != removed.name));
+
+    Ok(())
+}
+
+#[test]
+fn header_flags_are_not_rewritten() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    // values other than yes and no survive a round trip
+    let mut header = SaveHeader::try_from(bytes.as_ref())?;
+    assert_eq!(AllowedCommands::Yes, header.allowed_commands);
+    header.allowed_commands = AllowedCommands::Unknown(2);
+    let header2 = SaveHeader::try_from(&header.to_bytes()?[..])?;
+    assert_eq!(AllowedCommands::Unknown(2), header2.allowed_commands);
+
+    // plain flags can only be 0 or 1, so anything else is rejected rather than rewritten
+    let mut bytes = bytes;
+    let saving_replay = 0x1e;
+    assert_eq!(0, bytes[saving_replay]);
+    bytes[saving_replay] = 2;
+    match SaveHeader::try_from(bytes.as_ref()) {
+        Err(Error::Context { path, source, .. }) => {
+            assert_eq!("saving_replay", path);
+            assert!(matches!(*source, Error::Syntax(_)));
+        }
+        other => panic!("expected Context, got {:?}", other),
+    }
+
+    // as can the unused byte after the version, which is always written as 0
+    bytes[saving_replay] = 0;
+    bytes[8] = 1;
+    match SaveHeader::try_from(bytes.as_ref()) {
+        Err(Error::Context { path, source, .. }) => {
+            assert_eq!("_", path);
+            assert!(matches!(*source, Error::Syntax(_)));
+        }
+        other => panic!("expected Context, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn strict_mode_rejects_rest_of_level_init() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    let err = SaveHeader::parse(bytes.as_ref(), &ParseOptions::strict()).unwrap_err();
+    assert_eq!(&Error::TrailingBytes, err.kind());
+
+    Ok(())
+}
+
+#[test]
+fn lenient_mode_reports_end_of_header() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    // offset should land exactly at the end of the header
+    let parsed = SaveHeader::parse(bytes.as_ref(), &ParseOptions::lenient())?;
+    let header_bytes: Vec<u8> = parsed.value.try_into()?;
+    assert_eq!(header_bytes.len(), parsed.offset);
+
+    // and a header on its own should pass strict mode
+    SaveHeader::parse(header_bytes.as_ref(), &ParseOptions::strict())?;
+
+    Ok(())
+}
+
+#[test]
+fn reports_location_of_corrupt_mod() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let mut bytes = fs::read(path)?;
+
+    // make the second mod name invalid UTF-8
+    let header = SaveHeader::try_from(bytes.as_ref())?;
+    let name = header.mods[1].name.as_bytes();
+    let offset = bytes.windows(name.len()).position(|w| w == name).unwrap();
+    bytes[offset] = 0xFF;
+
+    match SaveHeader::try_from(bytes.as_ref()) {
+        Err(Error::Context {
+            offset: err_offset,
+            path,
+            source,
+        }) => {
+            assert_eq!("mods[1].name", path);
+            // the length prefix has been read by the time the bytes are checked
+            assert_eq!(offset, err_offset);
+            assert!(matches!(*source, Error::Utf8(_)));
+        }
+        other => panic!("expected Context, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn truncated_input_returns_eof() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+    let header_len = SaveHeader::parse(bytes.as_ref(), &ParseOptions::lenient())?.offset;
+
+    // every truncation of the header should fail cleanly rather than panic
+    for len in 0..header_len {
+        let err = SaveHeader::try_from(&bytes[..len]).unwrap_err();
+        assert_eq!(&Error::Eof, err.kind(), "truncated to {} bytes", len);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_from_reader() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(&path)?;
+
+    // reading should stop right at the end of the header
+    let mut cursor = Cursor::new(bytes.as_slice());
+    let header = SaveHeader::from_reader(&mut cursor)?;
+    let header_bytes: Vec<u8> = header.try_into()?;
+    assert_eq!(header_bytes.len() as u64, cursor.position());
+
+    // straight from the file too
+    SaveHeader::from_reader(fs::File::open(&path)?)?;
+    let path = Path::new("tests").join("vanilla.level-init.dat");
+    LevelInit::from_reader(fs::File::open(path)?)?;
+
+    Ok(())
+}
+
+#[test]
+fn can_compare_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let spaceage = SaveHeader::try_from(
+        fs::read(Path::new("tests").join("spaceage.level-init.dat"))?.as_ref(),
+    )?;
+    let vanilla = SaveHeader::try_from(
+        fs::read(Path::new("tests").join("vanilla.level-init.dat"))?.as_ref(),
+    )?;
+
+    let v2_0: Version = "2.0.0".parse()?;
+    assert!(spaceage.factorio_version >= v2_0);
+    assert!(vanilla.factorio_version < v2_0);
+    assert!(vanilla.factorio_version < spaceage.factorio_version);
+
+    Ok(())
+}
+
+/// Header with every field set to something other than its default, where the version allows
+fn header_for_version(version: Version) -> SaveHeader {
+    let is_2_0 = version >= Version::new(2, 0, 0, 0);
+    let after = |v: Version| version > v;
+    SaveHeader {
+        factorio_version: version.clone(),
+        campaign: "freeplay".to_owned(),
+        name: "level".to_owned(),
+        base_mod: "base".to_owned(),
+        difficulty: Difficulty::Normal,
+        finished: true,
+        player_won: true,
+        next_level: String::new(),
+        can_continue: after(Version::new(0, 12, 0, 0)),
+        finished_but_continuing: after(Version::new(0, 12, 0, 0)),
+        saving_replay: true,
+        allow_non_admin_debug_options: after(Version::new(0, 16, 0, 0)),
+        loaded_from: Version48::from(version.clone()),
+        loaded_from_build: if is_2_0 {
+            BuildNumber::Build32(300)
+        } else {
+            BuildNumber::Build16(300)
+        },
+        allowed_commands: AllowedCommands::Yes,
+        unknown_2_0: if is_2_0 { Some([1, 2, 3, 4]) } else { None },
+        mods: vec![SaveHeaderMod {
+            name: "base".to_owned(),
+            version: Version48::new(0, 300, 1),
+            crc: if after(Version::new(0, 15, 0, 91)) {
+                0xDEADBEEF
+            } else {
+                0
+            },
+        }],
+    }
+}
+
+#[test]
+fn can_round_trip_historic_header_layouts() -> Result<(), Box<dyn std::error::Error>> {
+    // one per layout change, plus either side of the thresholds
+    let versions = [
+        "0.12.0.0",
+        "0.12.35.0",
+        "0.13.20.0",
+        "0.14.13.0",
+        "0.14.14.0",
+        "0.15.0.91",
+        "0.15.40.0",
+        "0.16.0.0",
+        "0.16.51.0",
+        "0.17.0.0",
+        "0.17.79.0",
+        "0.18.47.0",
+        "1.1.110.0",
+        "2.0.28.0",
+    ];
+    for version in &versions {
+        let header = header_for_version(version.parse()?);
+        let bytes = header.to_bytes()?;
+        let header2 = SaveHeader::try_from(bytes.as_ref())?;
+        assert_eq!(bytes, header2.to_bytes()?, "{}", version);
+        assert_eq!(header.can_continue, header2.can_continue, "{}", version);
+        assert_eq!(header.mods[0].crc, header2.mods[0].crc, "{}", version);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_serialise_0_12_header_layout() -> Result<(), Box<dyn std::error::Error>> {
+    let header = header_for_version("0.12.0.0".parse()?);
+
+    // no unused byte, plain u32 lengths and u16 versions, none of the later fields
+    let mut expected = vec![0, 0, 12, 0, 0, 0, 0, 0];
+    expected.extend(&[8, 0, 0, 0]);
+    expected.extend(b"freeplay");
+    expected.extend(&[5, 0, 0, 0]);
+    expected.extend(b"level");
+    expected.extend(&[4, 0, 0, 0]);
+    expected.extend(b"base");
+    expected.extend(&[1, 1, 1]);
+    expected.extend(&[0, 0, 0, 0]);
+    expected.extend(&[1]);
+    expected.extend(&[0, 0, 12, 0, 0, 0]);
+    expected.extend(&[44, 1]);
+    expected.extend(&[1]);
+    expected.extend(&[1, 0, 0, 0]);
+    expected.extend(&[4, 0, 0, 0]);
+    expected.extend(b"base");
+    expected.extend(&[0, 0, 44, 1, 1, 0]);
+    assert_eq!(expected, header.to_bytes()?);
+
+    Ok(())
+}
+
+#[test]
+fn can_check_header_against_1_1_save() -> Result<(), Box<dyn std::error::Error>> {
+    // Any layout threshold in the wrong place misaligns the fields after it, so the
+    // header would fail to parse or be written back differently
+    let bytes = fs::read(Path::new("tests").join("vanilla.level-init.dat"))?;
+
+    let header = SaveHeader::try_from(bytes.as_ref())?;
+    assert_eq!(1, header.factorio_version.main());
+    assert_eq!(1, header.factorio_version.major());
+    assert_eq!("base", header.base_mod);
+    assert!(!matches!(header.difficulty, Difficulty::Unknown(_)));
+    assert!(!matches!(
+        header.allowed_commands,
+        AllowedCommands::Unknown(_)
+    ));
+
+    // the base mod always has the same version as the game
+    let base = header.mods.iter().find(|m| m.name == "base").unwrap();
+    assert_eq!(
+        Version48::from(header.factorio_version.clone()),
+        base.version
+    );
+
+    // written back exactly as the game wrote it
+    let written = header.to_bytes()?;
+    assert_eq!(&bytes[..written.len()], &written[..]);
+
+    Ok(())
+}
+
+#[test]
+fn fields_missing_from_layout_must_be_default() -> Result<(), Box<dyn std::error::Error>> {
+    let mut header = header_for_version("0.15.0.0".parse()?);
+    header.allow_non_admin_debug_options = true;
+
+    match header.to_bytes() {
+        Err(Error::Syntax(msg)) => assert!(msg.contains("allow_non_admin_debug_options")),
+        other => panic!("expected Syntax, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_peek_version() -> Result<(), Box<dyn std::error::Error>> {
+    for fixture in &[
+        "vanilla.level-init.dat",
+        "spaceage.level-init.dat",
+        "spaceage-withmods.level-init.dat",
+    ] {
+        let bytes = fs::read(Path::new("tests").join(fixture))?;
+        let header = SaveHeader::try_from(bytes.as_ref())?;
+        assert_eq!(header.factorio_version, SaveHeader::peek_version(&bytes)?);
+
+        // only the version itself is needed
+        assert_eq!(
+            header.factorio_version,
+            SaveHeader::peek_version(&bytes[..8])?
+        );
+    }
+
+    let err = SaveHeader::peek_version(&[1, 0, 1]).unwrap_err();
+    assert_eq!(&Error::Eof, err.kind());
+
+    Ok(())
+}
+
+#[test]
+fn can_parse_summary_without_mods() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+    let header = SaveHeader::try_from(bytes.as_ref())?;
+
+    // the mod list starts right after the summary fields, so cut it off entirely
+    let mut without_mods = header.clone();
+    without_mods.mods.clear();
+    let summary_len = without_mods.to_bytes()?.len() - 1;
+
+    let summary = SaveHeader::parse_summary(&bytes[..summary_len])?;
+    assert!(summary.mods.is_empty());
+    assert_eq!(header.factorio_version, summary.factorio_version);
+    assert_eq!(header.name, summary.name);
+    assert_eq!(header.loaded_from, summary.loaded_from);
+    assert_eq!(header.unknown_2_0, summary.unknown_2_0);
+
+    Ok(())
+}
+
+#[test]
+fn can_check_dependencies_against_save_mods() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let header = SaveHeader::try_from(fs::read(path)?.as_ref())?;
+
+    let dependencies = [
+        "base >= 2.0",
+        "flib >= 0.15.0",
+        "? space-age",
+        "? not-loaded >= 9.0.0",
+        "~ factoryplanner > 2.0.1",
+        "! Smart_Inserters",
+        "(?) FilterHelper < 0.2.0",
+        "missing-mod",
+    ]
+    .iter()
+    .map(|s| s.parse())
+    .collect::<Result<Vec<Dependency>, _>>()?;
+
+    let unmet: Vec<String> = header
+        .unmet_dependencies(&dependencies)
+        .iter()
+        .map(|d| d.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            "~ factoryplanner > 2.0.1",
+            "! Smart_Inserters",
+            "(?) FilterHelper < 0.2.0",
+            "missing-mod",
+        ],
+        unmet
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+struct DottedVersions {
+    #[serde(with = "factorio_file_parser::dotted")]
+    game: Version,
+    #[serde(with = "factorio_file_parser::dotted")]
+    loaded_from: Version48,
+}
+
+#[test]
+fn can_serialise_versions_as_dotted_strings() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage.level-init.dat");
+    let header = SaveHeader::try_from(fs::read(path)?.as_ref())?;
+
+    let versions = DottedVersions {
+        game: header.factorio_version.clone(),
+        loaded_from: header.loaded_from.clone(),
+    };
+    let json = serde_json::to_value(&versions)?;
+    assert_eq!(
+        serde_json::json!({
+            "game": header.factorio_version.to_string(),
+            "loaded_from": header.loaded_from.to_string(),
+        }),
+        json
+    );
+    assert_eq!(versions, serde_json::from_value(json)?);
+
+    assert!(
+        serde_json::from_str::<DottedVersions>(r#"{"game": "1.x", "loaded_from": "1.1.0"}"#)
+            .is_err()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_level_init_vanilla() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("vanilla.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    // no mods means no startup settings
+    let level_init = LevelInit::try_from(bytes.as_ref())?;
+    assert_eq!(0, level_init.startup_settings_crc);
+    match level_init.startup_settings {
+        PropertyTree::Dictionary(dict) => assert!(dict.is_empty()),
+        other => panic!("expected dictionary, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_and_serialise_level_init() -> Result<(), Box<dyn std::error::Error>> {
+    for fixture in &[
+        "vanilla.level-init.dat",
+        "spaceage.level-init.dat",
+        "spaceage-withmods.level-init.dat",
+    ] {
+        let path = Path::new("tests").join(fixture);
+        let bytes = fs::read(path)?;
+
+        // whole file should survive a round trip
+        let level_init = LevelInit::try_from(bytes.as_ref())?;
+        let bytes2: Vec<u8> = level_init.try_into()?;
+        assert_eq!(bytes, bytes2);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_read_map_gen_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = fs::read(Path::new("tests").join("vanilla.level-init.dat"))?;
+    let level_init = LevelInit::try_from(bytes.as_ref())?;
+    assert_eq!(12, level_init.before_map_gen_settings.len());
+    let settings = level_init.map_gen_settings.unwrap();
+    assert_eq!(Some(1.0), settings.terrain_segmentation);
+    assert_eq!(Some(1.0), settings.water);
+    assert_eq!(8, settings.autoplace_controls.len());
+    assert_eq!("coal", settings.autoplace_controls[0].0);
+    assert_eq!(3044849289, settings.seed);
+    assert_eq!((2000000, 2000000), (settings.width, settings.height));
+    assert_eq!(
+        MapPosition {
+            x: -224.0,
+            y: -224.0
+        },
+        settings.area_to_generate_at_start.left_top
+    );
+    assert_eq!(vec![MapPosition::default()], settings.starting_points);
+    assert_eq!("cliff", settings.cliff_settings.name);
+    assert_eq!(None, settings.cliff_settings.cliff_smoothing);
+
+    let bytes = fs::read(Path::new("tests").join("spaceage.level-init.dat"))?;
+    let level_init = LevelInit::try_from(bytes.as_ref())?;
+    assert_eq!(143, level_init.before_map_gen_settings.len());
+    let settings = level_init.map_gen_settings.unwrap();
+    assert_eq!(None, settings.water);
+    assert_eq!(28, settings.autoplace_controls.len());
+    assert_eq!(
+        (
+            "water".to_owned(),
+            AutoplaceControl {
+                frequency: 4.0 / 3.0,
+                size: 1.5,
+                richness: 1.0
+            }
+        ),
+        settings.autoplace_controls[27]
+    );
+    assert_eq!(1.5, settings.starting_area);
+    assert!(!settings.no_enemies_mode);
+    assert_eq!(Some(String::new()), settings.cliff_settings.control);
+    assert_eq!(Some(1.0), settings.cliff_settings.cliff_smoothing);
+
+    // cliffs disabled
+    let bytes = fs::read(Path::new("tests").join("spaceage-withmods.level-init.dat"))?;
+    let settings = LevelInit::try_from(bytes.as_ref())?
+        .map_gen_settings
+        .unwrap();
+    assert_eq!(1228309661, settings.seed);
+    assert_eq!("", settings.cliff_settings.name);
+
+    Ok(())
+}
+
+#[test]
+fn can_write_changed_map_gen_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = fs::read(Path::new("tests").join("spaceage.level-init.dat"))?;
+    let mut level_init = LevelInit::try_from(bytes.as_ref())?;
+    let remainder = level_init.remainder.clone();
+    let settings = level_init.map_gen_settings.as_mut().unwrap();
+    settings.seed = 12345;
+    settings.peaceful_mode = true;
+    settings
+        .starting_points
+        .push(MapPosition { x: 10.5, y: -3.0 });
+    let settings = settings.clone();
+
+    let bytes: Vec<u8> = level_init.try_into()?;
+    let level_init = LevelInit::try_from(bytes.as_ref())?;
+    assert_eq!(Some(settings), level_init.map_gen_settings);
+    assert_eq!(remainder, level_init.remainder);
+
+    // field only exists before 2.0
+    let mut level_init = level_init;
+    level_init.map_gen_settings.as_mut().unwrap().water = Some(1.0);
+    let result: Result<Vec<u8>, _> = level_init.try_into();
+    assert!(matches!(result, Err(Error::Syntax(_))));
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_level_init_without_alloc_limit() -> Result<(), Box<dyn std::error::Error>> {
+    // nothing is counted towards the limit before the remainder is read
+    let mut header = header_for_version(Version::new(2, 0, 8, 0));
+    header.campaign = String::new();
+    header.name = String::new();
+    header.base_mod = String::new();
+    header.mods.clear();
+    let mut bytes = header.to_bytes()?;
+    bytes.extend(&[0, 0, 0, 0]);
+    bytes.extend(&[5, 0, 0, 0, 0, 0]);
+    bytes.extend(b"rest of the map");
+
+    // usize::MAX is the obvious way to turn the limit off, and mustn't lose the remainder
+    let options = ParseOptions {
+        limits: ParseLimits {
+            max_total_alloc: usize::MAX,
+            ..ParseLimits::default()
+        },
+        ..ParseOptions::strict()
+    };
+    let level_init = LevelInit::parse(&bytes, &options)?.value;
+    assert_eq!(b"rest of the map", &level_init.remainder[..]);
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_2_0_integer_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    let level_init = LevelInit::try_from(bytes.as_ref())?;
+    assert_eq!(
+        Some(&PropertyTree::SignedInteger(3)),
+        level_init
+            .startup_settings
+            .pointer("/si-max-inserters-range/value")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reads_difficulty() -> Result<(), Box<dyn std::error::Error>> {
+    for name in &[
+        "vanilla.level-init.dat",
+        "spaceage.level-init.dat",
+        "spaceage-withmods.level-init.dat",
+    ] {
+        let bytes = fs::read(Path::new("tests").join(name))?;
+        let header = SaveHeader::try_from(bytes.as_ref())?;
+        assert_eq!(Difficulty::Normal, header.difficulty, "{}", name);
+        assert_eq!("Normal", header.difficulty.to_string());
+    }
+
+    // values the game isn't known to write survive a round trip
+    let mut header = header_for_version(Version::new(2, 0, 8, 0));
+    header.difficulty = Difficulty::Unknown(9);
+    let header2 = SaveHeader::try_from(&header.to_bytes()?[..])?;
+    assert_eq!(Difficulty::Unknown(9), header2.difficulty);
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_from_save_zip() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let bytes = fs::read(path)?;
+    let zip = build_save_zip(&[
+        ("my save/control.lua", b"-- control"),
+        ("my save/level-init.dat", &bytes),
+    ])?;
+
+    // attempt to deserialise from inside the zip
+    let from_zip = SaveHeader::from_save_zip(Cursor::new(zip))?;
+    let from_bytes = SaveHeader::try_from(bytes.as_ref())?;
+    assert_eq!(from_bytes.name, from_zip.name);
+    assert_eq!(from_bytes.mods.len(), from_zip.mods.len());
+
+    Ok(())
+}
+
+#[test]
+fn from_save_zip_rejects_non_zip() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage.level-init.dat");
+    let bytes = fs::read(path)?;
+
+    match SaveHeader::from_save_zip(Cursor::new(bytes)) {
+        Err(Error::NotZip(_)) => Ok(()),
+        other => panic!("expected NotZip, got {:?}", other),
+    }
+}
+
+#[test]
+fn from_save_zip_reports_missing_header() -> Result<(), Box<dyn std::error::Error>> {
+    let zip = build_save_zip(&[
+        ("my save/level.dat0", b"not a header"),
+        ("my save/nested/level-init.dat", b"too deep"),
+    ])?;
+
+    assert_eq!(
+        Err(Error::SaveHeaderMissing),
+        SaveHeader::from_save_zip(Cursor::new(zip)).map(|_| ())
+    );
+
+    Ok(())
+}
@@ -0,0 +1,98 @@
+use std::{convert::TryFrom, fs, path::Path};
+
+use factorio_file_parser::{Error, ModList, ModListEntry, SaveHeader, Version48};
+
+const SAMPLE: &str = r#"{
+  "mods": [
+    {
+      "name": "base",
+      "enabled": true
+    },
+    {
+      "name": "flib",
+      "enabled": true,
+      "version": "0.14.0"
+    },
+    {
+      "name": "unused-mod",
+      "enabled": false
+    }
+  ]
+}"#;
+
+#[test]
+fn can_deserialise_and_serialise_sample() -> Result<(), Box<dyn std::error::Error>> {
+    let mod_list = ModList::from_reader(SAMPLE.as_bytes())?;
+    assert_eq!(3, mod_list.mods.len());
+    assert_eq!(
+        Some(&ModListEntry {
+            name: "flib".to_owned(),
+            enabled: true,
+            version: Some(Version48::new(0, 14, 0)),
+        }),
+        mod_list.get("flib")
+    );
+    assert_eq!(None, mod_list.get("base").and_then(|m| m.version.as_ref()));
+
+    // written the same way the game writes it
+    assert_eq!(SAMPLE.as_bytes(), &mod_list.to_bytes()?[..]);
+
+    Ok(())
+}
+
+#[test]
+fn invalid_json_is_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let err = ModList::from_reader(&br#"{"mods": [{"name": "base"}]}"#[..]).unwrap_err();
+    assert!(matches!(err, Error::Syntax(_)));
+
+    let err = ModList::from_reader(&SAMPLE.as_bytes()[..20]).unwrap_err();
+    assert_eq!(Error::Eof, err);
+
+    let bad_version = r#"{"mods": [{"name": "base", "enabled": true, "version": "1.x"}]}"#;
+    let err = ModList::from_reader(bad_version.as_bytes()).unwrap_err();
+    assert!(matches!(err, Error::Syntax(_)));
+
+    Ok(())
+}
+
+#[test]
+fn can_build_from_save_header() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let header = SaveHeader::try_from(fs::read(path)?.as_ref())?;
+
+    let mod_list = ModList::from_save_header(&header);
+    assert_eq!(header.mods.len(), mod_list.mods.len());
+    for (save_mod, entry) in header.mods.iter().zip(&mod_list.mods) {
+        assert_eq!(save_mod.name, entry.name);
+        assert!(entry.enabled);
+        assert_eq!(Some(&save_mod.version), entry.version.as_ref());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_sync_to_save_header() -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new("tests").join("spaceage-withmods.level-init.dat");
+    let header = SaveHeader::try_from(fs::read(path)?.as_ref())?;
+
+    let mut mod_list = ModList::from_reader(SAMPLE.as_bytes())?;
+    mod_list.sync_to_save_header(&header);
+
+    // existing entries keep their place, new ones are added after
+    assert_eq!(header.mods.len() + 1, mod_list.mods.len());
+    assert_eq!("base", mod_list.mods[0].name);
+    assert_eq!("unused-mod", mod_list.mods[2].name);
+    assert!(!mod_list.mods[2].enabled);
+
+    // pinned to the version in the save
+    let flib = mod_list.get("flib").unwrap();
+    assert!(flib.enabled);
+    assert_eq!(Some(Version48::new(0, 15, 0)), flib.version);
+
+    // should survive a round trip
+    let mod_list2 = ModList::from_reader(&mod_list.to_bytes()?[..])?;
+    assert_eq!(mod_list, mod_list2);
+
+    Ok(())
+}
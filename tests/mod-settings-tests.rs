@@ -1,6 +1,9 @@
-use std::{convert::{TryFrom, TryInto}, fs, path::Path};
+use std::{convert::{TryFrom, TryInto}, fs, io::{Read, Write}, path::Path};
 
-use factorio_file_parser::ModSettings;
+use factorio_file_parser::{
+    Color, Error, ModSettings, ParseLimits, ParseOptions, PropertyTree, SettingValue,
+    SettingsSection, Version,
+};
 
 #[test]
 fn can_deserialise_sample() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,19 +29,95 @@ fn can_deserialise_and_serialise_sample() -> Result<(), Box<dyn std::error::Erro
     // serialise back
     let bytes2: Vec<u8> = ms.try_into()?;
 
-    // this likely won't be equal because the sample is taken from a game, with unknown propertytree ordering
-    // so we deserialise again
-    let ms2 = ModSettings::try_from(bytes2.as_ref())?;
+    // ordering and any-type flags are preserved, so this should match the game's output exactly
+    assert_eq!(bytes, bytes2);
+
+    Ok(())
+}
+
+/// Hands out one byte per read, like a slow socket
+struct OneByteReader<'a>(&'a [u8]);
+
+impl Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((b, rest)), Some(out)) => {
+                *out = *b;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
 
-    // then serialise again
+#[test]
+fn can_deserialise_from_reader() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(&path)?;
+
+    // parse a byte at a time, and straight from the file
+    let ms = ModSettings::from_reader(OneByteReader(&bytes))?;
+    let ms2 = ModSettings::from_reader(fs::File::open(&path)?)?;
+
+    // both should match parsing the whole buffer
+    let expected: Vec<u8> = ModSettings::try_from(bytes.as_ref())?.try_into()?;
+    let bytes2: Vec<u8> = ms.try_into()?;
     let bytes3: Vec<u8> = ms2.try_into()?;
+    assert_eq!(expected, bytes2);
+    assert_eq!(expected, bytes3);
+
+    Ok(())
+}
+
+#[test]
+fn can_write_to_file() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    // write straight to a file without giving up ownership
+    let out_path = std::env::temp_dir().join("factorio-file-parser-write-to.dat");
+    ms.write_to(fs::File::create(&out_path)?)?;
+    let written = fs::read(&out_path)?;
+    fs::remove_file(&out_path)?;
 
-    // now assert byte-by-byte equality
-    assert_eq!(bytes2, bytes3);
+    // should match the owned serialisation
+    assert_eq!(ms.to_bytes()?, written);
+    let bytes2: Vec<u8> = ms.try_into()?;
+    assert_eq!(bytes2, written);
 
     Ok(())
 }
 
+/// Fails every write, like a full disk
+struct FailingWriter;
+
+impl Write for FailingWriter {
+    fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+        Err(std::io::Error::other("disk full"))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_errors_are_reported() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    match ms.write_to(FailingWriter) {
+        Err(Error::Io(_)) => Ok(()),
+        other => panic!("expected Io, got {:?}", other),
+    }
+}
+
 #[test]
 fn can_serialise_to_json() -> Result<(), Box<dyn std::error::Error>> {
     // read file
@@ -53,3 +132,526 @@ fn can_serialise_to_json() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn can_read_typed_settings() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    let startup = SettingsSection::Startup;
+    assert_eq!(
+        Some(SettingValue::Bool(true)),
+        ms.get(startup, "landfillpainting-use-rotation")
+    );
+    assert_eq!(
+        Some(SettingValue::String("noadjustment".to_owned())),
+        ms.get(startup, "sct-difficulty-cost")
+    );
+    assert_eq!(
+        Some(SettingValue::Int(40000)),
+        ms.get(startup, "angels-starting-resource-base")
+    );
+    assert_eq!(
+        Some(SettingValue::Double(0.05)),
+        ms.get(startup, "bobmods-enemies-leviathanfrequency")
+    );
+    assert_eq!(
+        Some(SettingValue::Double(0.8)),
+        ms.get(
+            SettingsSection::RuntimePerUser,
+            "helmod_display_ratio_vertical"
+        )
+    );
+
+    // present, but in a different section
+    assert_eq!(
+        None,
+        ms.get(SettingsSection::RuntimeGlobal, "sct-difficulty-cost")
+    );
+    assert_eq!(None, ms.get(startup, "no-such-setting"));
+
+    Ok(())
+}
+
+#[test]
+fn can_query_property_tree_by_pointer() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    assert_eq!(
+        Some("noadjustment"),
+        ms.startup
+            .pointer("/sct-difficulty-cost/value")
+            .and_then(|t| t.as_str())
+    );
+    assert_eq!(
+        Some(true),
+        ms.startup["landfillpainting-use-rotation"]["value"].as_bool()
+    );
+    assert_eq!(None, ms.startup.pointer("/sct-difficulty-cost/missing"));
+
+    Ok(())
+}
+
+#[test]
+fn can_edit_typed_settings() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+
+    let startup = SettingsSection::Startup;
+    ms.set(
+        startup,
+        "landfillpainting-use-rotation",
+        SettingValue::Bool(false),
+    )?;
+    ms.set(startup, "brand-new-setting", SettingValue::Int(7))?;
+    let removed = ms.remove(startup, "sct-difficulty-cost");
+    assert_eq!(
+        Some(SettingValue::String("noadjustment".to_owned())),
+        removed
+    );
+
+    // edits should survive a round trip
+    let bytes2 = ms.to_bytes()?;
+    let ms2 = ModSettings::try_from(bytes2.as_ref())?;
+    assert_eq!(
+        Some(SettingValue::Bool(false)),
+        ms2.get(startup, "landfillpainting-use-rotation")
+    );
+    assert_eq!(
+        Some(SettingValue::Int(7)),
+        ms2.get(startup, "brand-new-setting")
+    );
+    assert_eq!(None, ms2.get(startup, "sct-difficulty-cost"));
+
+    Ok(())
+}
+
+#[test]
+fn can_round_trip_color_setting() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+
+    let color = Color {
+        r: 1.0,
+        g: 0.5,
+        b: 0.25,
+        a: 1.0,
+    };
+    ms.set(
+        SettingsSection::RuntimePerUser,
+        "example-color",
+        SettingValue::Color(color),
+    )?;
+
+    // should come back as a color rather than a plain dictionary
+    let ms2 = ModSettings::try_from(ms.to_bytes()?.as_ref())?;
+    assert_eq!(
+        Some(SettingValue::Color(color)),
+        ms2.get(SettingsSection::RuntimePerUser, "example-color")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn can_deserialise_and_serialise_legacy_layout() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    // files from before 0.17 have no version or sentinel
+    let legacy_bytes = &bytes[9..];
+    let legacy = ModSettings::try_from(legacy_bytes)?;
+    assert_eq!(None, legacy.version);
+    assert_eq!(ms.startup, legacy.startup);
+    assert_eq!(ms.runtime_global, legacy.runtime_global);
+    assert_eq!(ms.runtime_per_user, legacy.runtime_per_user);
+
+    // written back without a version, unless upgraded
+    assert_eq!(legacy_bytes, &legacy.to_bytes()?[..]);
+    let upgraded = legacy.convert_to(ms.version.clone().unwrap())?;
+    assert_eq!(bytes, upgraded.to_bytes()?);
+
+    Ok(())
+}
+
+#[test]
+fn keeps_top_level_any_type_flag() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    assert!(!ModSettings::try_from(bytes.as_ref())?.any_type);
+
+    // flag follows the dictionary type byte, which comes after the version and
+    // sentinel, or first in files from before 0.17
+    for (file, dictionary) in &[(&bytes[..], 9), (&bytes[9..], 0)] {
+        let mut flagged = file.to_vec();
+        assert_eq!([5, 0], flagged[*dictionary..*dictionary + 2]);
+        flagged[*dictionary + 1] = 1;
+
+        let ms = ModSettings::try_from(flagged.as_ref())?;
+        assert!(ms.any_type);
+        assert_eq!(*dictionary != 0, ms.version.is_some());
+        assert_eq!(flagged, ms.to_bytes()?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn can_peek_version() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+
+    assert_eq!(ms.version, ModSettings::peek_version(&bytes)?);
+    assert_eq!(ms.version, ModSettings::peek_version(&bytes[..8])?);
+
+    // files from before 0.17 have no version to peek at
+    assert_eq!(None, ModSettings::peek_version(&bytes[9..])?);
+
+    Ok(())
+}
+
+#[test]
+fn strict_mode_rejects_trailing_bytes() -> Result<(), Box<dyn std::error::Error>> {
+    // read file, then append junk
+    let path = Path::new("tests").join("mod-settings.dat");
+    let mut bytes = fs::read(path)?;
+    bytes.extend(&[0xDE, 0xAD]);
+
+    let err = ModSettings::parse(bytes.as_ref(), &ParseOptions::strict()).unwrap_err();
+    assert_eq!(&Error::TrailingBytes, err.kind());
+
+    Ok(())
+}
+
+#[test]
+fn lenient_mode_reports_offset() -> Result<(), Box<dyn std::error::Error>> {
+    // read file, then append junk
+    let path = Path::new("tests").join("mod-settings.dat");
+    let mut bytes = fs::read(path)?;
+    let len = bytes.len();
+    bytes.extend(&[0xDE, 0xAD]);
+
+    // parsing should stop right where the junk starts
+    let parsed = ModSettings::parse(bytes.as_ref(), &ParseOptions::lenient())?;
+    assert_eq!(len, parsed.offset);
+
+    Ok(())
+}
+
+#[test]
+fn reports_location_of_corrupt_setting() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let mut bytes = fs::read(path)?;
+
+    // find the value of the first setting, and give it an invalid PropertyTreeType
+    let setting = b"landfillpainting-use-rotation";
+    let setting_offset = bytes
+        .windows(setting.len())
+        .position(|w| w == setting)
+        .unwrap();
+    let value = b"value";
+    let value_offset = setting_offset
+        + bytes[setting_offset..]
+            .windows(value.len())
+            .position(|w| w == value)
+            .unwrap()
+        + value.len();
+    bytes[value_offset] = 0x7F;
+
+    match ModSettings::try_from(bytes.as_ref()) {
+        Err(Error::Context {
+            offset,
+            path,
+            source,
+        }) => {
+            assert_eq!("startup/landfillpainting-use-rotation/value", path);
+            // type byte and any-type flag have been read by the time the type is checked
+            assert_eq!(value_offset + 2, offset);
+            assert_eq!(Error::OutOfRange, *source);
+        }
+        other => panic!("expected Context, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn truncated_input_returns_eof() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+
+    // every truncation should fail cleanly rather than panic
+    for len in 0..bytes.len() {
+        let err = ModSettings::try_from(&bytes[..len]).unwrap_err();
+        assert_eq!(&Error::Eof, err.kind(), "truncated to {} bytes", len);
+    }
+
+    Ok(())
+}
+
+/// Version 1.1.0.0 followed by the false sentinel
+fn settings_preamble() -> Vec<u8> {
+    vec![1, 0, 1, 0, 0, 0, 0, 0, 0]
+}
+
+#[test]
+fn deep_nesting_exceeds_limit() -> Result<(), Box<dyn std::error::Error>> {
+    // single-element lists nested far deeper than the default limit
+    let mut bytes = settings_preamble();
+    for _ in 0..100_000 {
+        bytes.extend(&[4, 0, 1, 0, 0, 0, 1]);
+    }
+
+    let err = ModSettings::try_from(bytes.as_ref()).unwrap_err();
+    assert!(matches!(err.kind(), Error::LimitExceeded(_)));
+
+    Ok(())
+}
+
+#[test]
+fn preserves_any_type_flag() -> Result<(), Box<dyn std::error::Error>> {
+    // top-level dictionary of the three sections, with the flag set on one of them
+    let mut bytes = settings_preamble();
+    bytes.extend(&[5, 0, 3, 0, 0, 0]);
+    bytes.extend(&[0, 7]);
+    bytes.extend(b"startup");
+    bytes.extend(&[5, 1, 0, 0, 0, 0]);
+    bytes.extend(&[0, 14]);
+    bytes.extend(b"runtime-global");
+    bytes.extend(&[5, 0, 0, 0, 0, 0]);
+    bytes.extend(&[0, 16]);
+    bytes.extend(b"runtime-per-user");
+    bytes.extend(&[5, 0, 0, 0, 0, 0]);
+
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+    assert!(ms.startup.is_any_type());
+    assert!(!ms.runtime_global.is_any_type());
+    assert!(ms.startup.as_dict().is_some_and(|d| d.is_empty()));
+
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    Ok(())
+}
+
+/// Three empty settings sections, with `startup` holding `setting` set to `value`
+fn settings_with_value(version_main: u8, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![version_main, 0, 0, 0, 0, 0, 0, 0, 0];
+    bytes.extend(&[5, 0, 3, 0, 0, 0]);
+    bytes.extend(&[0, 7]);
+    bytes.extend(b"startup");
+    bytes.extend(&[5, 0, 1, 0, 0, 0]);
+    bytes.extend(&[0, 7]);
+    bytes.extend(b"setting");
+    bytes.extend(&[5, 0, 1, 0, 0, 0]);
+    bytes.extend(&[0, 5]);
+    bytes.extend(b"value");
+    bytes.extend(value);
+    bytes.extend(&[0, 14]);
+    bytes.extend(b"runtime-global");
+    bytes.extend(&[5, 0, 0, 0, 0, 0]);
+    bytes.extend(&[0, 16]);
+    bytes.extend(b"runtime-per-user");
+    bytes.extend(&[5, 0, 0, 0, 0, 0]);
+    bytes
+}
+
+#[test]
+fn can_round_trip_2_0_integer_types() -> Result<(), Box<dyn std::error::Error>> {
+    let startup = SettingsSection::Startup;
+
+    // signed 64-bit int
+    let bytes = settings_with_value(2, &[6, 0, 0xFD, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    assert_eq!(Some(SettingValue::Int(-3)), ms.get(startup, "setting"));
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    // int settings stay integers when changed
+    ms.set(startup, "setting", SettingValue::Int(4))?;
+    let expected = settings_with_value(2, &[6, 0, 4, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(expected, ms.to_bytes()?);
+
+    // unsigned 64-bit int
+    let bytes = settings_with_value(2, &[7, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    assert_eq!(
+        Some(u64::MAX),
+        ms.startup
+            .pointer("/setting/value")
+            .and_then(|t| t.as_u64())
+    );
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    // and unsigned ones stay unsigned
+    ms.set(startup, "setting", SettingValue::Int(5))?;
+    let expected = settings_with_value(2, &[7, 0, 5, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(Some(SettingValue::Int(5)), ms.get(startup, "setting"));
+    assert_eq!(expected, ms.to_bytes()?);
+
+    // double settings holding a whole number stay doubles
+    let bytes = settings_with_value(2, &[2, 0, 0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    let value = ms.get(startup, "setting").unwrap();
+    assert_eq!(SettingValue::Int(1), value);
+    ms.set(startup, "setting", value)?;
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    // new settings are added as integers
+    ms.set(startup, "new-setting", SettingValue::Int(1))?;
+    assert_eq!(
+        Some(&PropertyTree::SignedInteger(1)),
+        ms.startup.pointer("/new-setting/value")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn set_keeps_any_type_settings() -> Result<(), Box<dyn std::error::Error>> {
+    let startup = SettingsSection::Startup;
+    let bytes = settings_with_value(1, &[2, 0, 0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    let setting = ms.startup.get_mut("setting").unwrap();
+    setting.insert("extra", PropertyTree::Bool(true))?;
+    *setting = PropertyTree::AnyType(Box::new(setting.clone()));
+
+    ms.set(startup, "setting", SettingValue::Double(0.5))?;
+    let setting = ms.startup.get("setting").unwrap();
+    assert!(setting.is_any_type());
+    assert_eq!(Some(&PropertyTree::Number(0.5)), setting.get("value"));
+    assert_eq!(Some(&PropertyTree::Bool(true)), setting.get("extra"));
+
+    // and the flag survives being written
+    let ms = ModSettings::try_from(ms.to_bytes()?.as_ref())?;
+    assert!(ms.startup.get("setting").unwrap().is_any_type());
+    assert_eq!(Some(SettingValue::Double(0.5)), ms.get(startup, "setting"));
+
+    Ok(())
+}
+
+#[test]
+fn rejects_ints_that_are_not_exact_as_doubles() -> Result<(), Box<dyn std::error::Error>> {
+    let startup = SettingsSection::Startup;
+    let too_big = SettingValue::Int((1 << 53) + 1);
+
+    // before 2.0 there are only doubles
+    let bytes = settings_with_value(1, &[2, 0, 0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    assert!(matches!(
+        ms.set(startup, "setting", too_big.clone()),
+        Err(Error::Syntax(_))
+    ));
+    assert!(ms.set(startup, "new-setting", too_big.clone()).is_err());
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    // 2.0 double settings stay doubles, so can't hold it either
+    let bytes = settings_with_value(2, &[2, 0, 0, 0, 0, 0, 0, 0, 0xF0, 0x3F]);
+    let mut ms = ModSettings::try_from(bytes.as_ref())?;
+    assert!(ms.set(startup, "setting", too_big.clone()).is_err());
+    assert_eq!(bytes, ms.to_bytes()?);
+
+    // but new 2.0 settings are integers
+    ms.set(startup, "new-setting", too_big.clone())?;
+    assert_eq!(Some(too_big), ms.get(startup, "new-setting"));
+
+    Ok(())
+}
+
+#[test]
+fn can_convert_between_versions() -> Result<(), Box<dyn std::error::Error>> {
+    let v1_0 = Version::new(1, 0, 0, 0);
+    let v2_0 = Version::new(2, 0, 0, 0);
+
+    // integers become doubles going back to 1.0
+    let bytes = settings_with_value(2, &[6, 0, 4, 0, 0, 0, 0, 0, 0, 0]);
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+    let converted = ms.convert_to(v1_0)?;
+    let expected = settings_with_value(1, &[2, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x40]);
+    assert_eq!(expected, converted.to_bytes()?);
+
+    // and stay doubles going forward again
+    let mut expected = expected;
+    expected[0] = 2;
+    assert_eq!(expected, converted.convert_to(v2_0)?.to_bytes()?);
+
+    Ok(())
+}
+
+#[test]
+fn conversion_fails_for_inexact_integers() -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = settings_with_value(2, &[7, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    let ms = ModSettings::try_from(bytes.as_ref())?;
+    match ms.convert_to(Version::new(1, 0, 0, 0)) {
+        Err(Error::Syntax(msg)) => assert!(msg.contains("/startup/setting/value")),
+        other => panic!("expected Syntax, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn integer_types_rejected_before_2_0() -> Result<(), Box<dyn std::error::Error>> {
+    let bytes = settings_with_value(1, &[6, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+    let err = ModSettings::try_from(bytes.as_ref()).unwrap_err();
+    assert!(matches!(err.kind(), Error::Syntax(_)));
+
+    Ok(())
+}
+
+#[test]
+fn huge_entry_count_exceeds_limit() -> Result<(), Box<dyn std::error::Error>> {
+    // dictionary claiming u32::MAX entries, with none following
+    let mut bytes = settings_preamble();
+    bytes.extend(&[5, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+
+    let err = ModSettings::try_from(bytes.as_ref()).unwrap_err();
+    assert!(matches!(err.kind(), Error::LimitExceeded(_)));
+
+    Ok(())
+}
+
+#[test]
+fn custom_limits_are_enforced() -> Result<(), Box<dyn std::error::Error>> {
+    // read file
+    let path = Path::new("tests").join("mod-settings.dat");
+    let bytes = fs::read(path)?;
+
+    // sample has plenty of strings longer than this
+    let options = ParseOptions {
+        limits: ParseLimits {
+            max_string_len: 4,
+            ..ParseLimits::default()
+        },
+        ..ParseOptions::strict()
+    };
+    let err = ModSettings::parse(bytes.as_ref(), &options).unwrap_err();
+    assert!(matches!(err.kind(), Error::LimitExceeded(_)));
+
+    // and the total allocation limit is hit long before the end of the file
+    let options = ParseOptions {
+        limits: ParseLimits {
+            max_total_alloc: 1024,
+            ..ParseLimits::default()
+        },
+        ..ParseOptions::strict()
+    };
+    let err = ModSettings::parse(bytes.as_ref(), &options).unwrap_err();
+    assert!(matches!(err.kind(), Error::LimitExceeded(_)));
+
+    Ok(())
+}